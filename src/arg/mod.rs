@@ -4,13 +4,27 @@ pub mod profile;
 pub mod show;
 pub mod status;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 /// A CLI tool for Mihomo
 #[derive(Parser)]
 #[command(about, version, long_about = None)]
 #[command(propagate_version = true)]
-pub enum Args {
+pub struct Args {
+    /// Assume yes for all confirm prompts
+    #[arg(long, short, global = true)]
+    pub yes: bool,
+
+    /// Show tokens and secret parts of URLs instead of redacting them
+    #[arg(long, global = true)]
+    pub show_secrets: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
     /// Mihomosh configuration subcommand
     #[command(subcommand)]
     Config(config::Command),
@@ -19,6 +33,9 @@ pub enum Args {
     #[command(subcommand)]
     Ctrl(ctrl::Command),
 
+    /// Auto update remote profiles on schedule
+    Daemon,
+
     /// Mihomo profiles managing subcommand
     #[command(subcommand)]
     Profile(profile::Command),
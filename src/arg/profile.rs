@@ -1,4 +1,8 @@
-use clap::Subcommand;
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+
+use crate::{data::config::ConfigActivationMethod, utils::output::OutputFormat};
 
 #[derive(Subcommand)]
 pub enum Command {
@@ -6,6 +10,41 @@ pub enum Command {
     Activate {
         /// Profile UUID or name
         uuid_or_name: String,
+
+        /// Keep watching the profile data file and re-activate on changes
+        #[arg(long, short)]
+        watch: bool,
+
+        /// Show differences against the current Mihomo config without activating
+        #[arg(long, conflicts_with = "watch")]
+        dry_run: bool,
+
+        /// Activate even if the rendered config has problems
+        #[arg(long)]
+        force: bool,
+
+        /// How to apply the profile (Default: `activation_method` in global configs)
+        #[arg(long, value_enum)]
+        method: Option<ConfigActivationMethod>,
+
+        /// Download `http` providers and rewrite them to local files
+        /// (Default: `localize_providers` in global configs)
+        #[arg(long)]
+        localize: bool,
+    },
+
+    /// List profile activation logs
+    Activations {
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
+
+    /// Show the currently activated profile
+    Current {
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        output: OutputFormat,
     },
 
     /// Delete a profile
@@ -15,25 +54,109 @@ pub enum Command {
         uuid_or_name: String,
     },
 
+    /// Show differences between a profile's history revisions
+    Diff {
+        /// Profile UUID or name
+        uuid_or_name: String,
+
+        /// Old revision (Default: the previous revision)
+        old: Option<usize>,
+
+        /// New revision (Default: current files)
+        new: Option<usize>,
+    },
+
     /// Edit a profile's configurations
     EditConfigs {
         /// Profile UUID or name
         uuid_or_name: String,
+
+        #[command(flatten)]
+        conf: ConfigArgs,
     },
 
     /// Edit a profile's data
     EditData {
         /// Profile UUID or name
         uuid_or_name: String,
+
+        /// Read data from a file instead of the editor, `-` for stdin
+        #[arg(long)]
+        from_file: Option<PathBuf>,
+    },
+
+    /// Export profiles into a bundle file
+    Export {
+        /// Profile UUIDs or names
+        #[arg(required = true)]
+        uuid_or_names: Vec<String>,
+
+        /// Bundle file path, `-` for stdout
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// Strip URLs and credentials, remote profiles are exported as local ones
+        #[arg(long)]
+        strip_secrets: bool,
+    },
+
+    /// List a profile's history revisions
+    History {
+        /// Profile UUID or name
+        uuid_or_name: String,
+    },
+
+    /// Import profiles from a bundle file
+    Import {
+        /// Bundle file path, `-` for stdin
+        bundle: PathBuf,
+    },
+
+    /// Import profiles from a Clash Verge (Rev) installation
+    ImportVerge {
+        /// Clash Verge data directory which contains `profiles.yaml`
+        dir: PathBuf,
+    },
+
+    /// Show a profile's information
+    Info {
+        /// Profile UUID or name
+        uuid_or_name: String,
     },
 
     /// List profiles
     #[command(visible_alias = "ls")]
-    List,
+    List {
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
 
     /// Create a new profile
     #[command(visible_alias = "add")]
-    New,
+    New {
+        #[command(flatten)]
+        conf: ConfigArgs,
+    },
+
+    /// Render a profile's Mihomo config without activating
+    Render {
+        /// Profile UUID or name
+        uuid_or_name: String,
+
+        /// Write the rendered config to a file instead of showing it, `-` for stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Rollback a profile to a history revision
+    Rollback {
+        /// Profile UUID or name
+        uuid_or_name: String,
+
+        /// Revision to rollback to
+        rev: usize,
+    },
 
     /// Update a profile or all profiles
     #[command(visible_alias = "up")]
@@ -60,3 +183,56 @@ pub enum Command {
         uuid_or_name: String,
     },
 }
+
+/// Profile configurations given on the command line, skipping the editor
+#[derive(Args)]
+pub struct ConfigArgs {
+    /// Profile name
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Profile type
+    #[arg(long = "type", value_parser = ["remote", "local", "file", "composite"])]
+    pub r#type: Option<String>,
+
+    /// Subscription URL of a remote profile
+    #[arg(long)]
+    pub url: Option<String>,
+
+    /// User agent used to fetch a remote profile
+    #[arg(long)]
+    pub user_agent: Option<String>,
+
+    /// External YAML path of a file profile
+    #[arg(long)]
+    pub path: Option<String>,
+
+    /// Read configurations from a file instead of the editor, `-` for stdin
+    #[arg(long)]
+    pub from_file: Option<PathBuf>,
+}
+impl ConfigArgs {
+    /// Whether any configuration is given, so that the editor should be skipped
+    pub fn is_set(&self) -> bool {
+        self.name.is_some()
+            || self.r#type.is_some()
+            || self.url.is_some()
+            || self.user_agent.is_some()
+            || self.path.is_some()
+            || self.from_file.is_some()
+    }
+
+    /// Configuration fields to set, in the order they appear in a profile
+    pub fn get_fields(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("name", &self.name),
+            ("type", &self.r#type),
+            ("path", &self.path),
+            ("url", &self.url),
+            ("user_agent", &self.user_agent),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| v.as_deref().map(|v| (k, v)))
+        .collect()
+    }
+}
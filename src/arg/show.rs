@@ -1,10 +1,16 @@
 use clap::Subcommand;
 
+use crate::utils::output::OutputFormat;
+
 #[derive(Subcommand)]
 pub enum Command {
     /// Show Mihomo current activated profile
     Profile,
 
     /// Show mihomo current activated rules
-    Rules,
+    Rules {
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
 }
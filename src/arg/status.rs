@@ -1,16 +1,30 @@
 use clap::Subcommand;
 
+use crate::utils::output::OutputFormat;
+
 #[derive(Subcommand)]
 pub enum Command {
     /// Get Mihomo running configurations
     Configs,
 
     /// Get Mihomo connections
-    Connections,
+    Connections {
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
 
     /// Get Mihomo groups
-    Groups,
+    Groups {
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
 
     /// Get Mihomo version
-    Version,
+    Version {
+        /// Output format
+        #[arg(long, short, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
 }
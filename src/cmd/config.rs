@@ -5,7 +5,7 @@ use anyhow::{Context, Result};
 use crate::{
     data::config::Config,
     utils::{
-        file, prompt,
+        self, file, prompt,
         result::{normal, success},
     },
 };
@@ -20,7 +20,7 @@ pub fn edit(editor: Option<String>) -> Result<()> {
     let contents =
         file::edit_temp_file(".yaml", editor.or(Some(cfg.editor.clone())), Some(contents))
             .with_context(|| "try to edit temporary contents")?;
-    serde_yaml::from_str::<Config>(&contents).with_context(|| "try to parse temporary contents")?;
+    Config::from_str(&contents).with_context(|| "try to parse temporary contents")?;
 
     // Confirm to save
     if !prompt::confirm("Are you sure to save the new configuration?")
@@ -30,7 +30,7 @@ pub fn edit(editor: Option<String>) -> Result<()> {
     }
 
     // Save configs
-    fs::write(&path, contents)
+    file::write_private(&path, contents)
         .with_context(|| format!("try to write file `{}`", path.display()))?;
 
     // Success
@@ -49,7 +49,7 @@ pub fn reset() -> Result<()> {
 
     // Reset configs
     let path = cfg.get_path();
-    fs::write(&path, Config::DEFAULT_CONFIG)
+    file::write_private(&path, Config::DEFAULT_CONFIG)
         .with_context(|| format!("try to write file `{}`", path.display()))?;
 
     // Success
@@ -59,9 +59,21 @@ pub fn reset() -> Result<()> {
 pub fn view() -> Result<()> {
     let cfg = Config::get_instance();
 
-    // Show configs
+    // Redact token
     let path = cfg.get_path();
-    file::show_file(&path).with_context(|| format!("try to show file `{}`", path.display()))?;
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("try to read file `{}`", path.display()))?;
+    let contents = utils::redact_yaml(&contents, |keys, _| {
+        (keys == ["mihomo_token"]).then(|| "******".to_owned())
+    });
+
+    // Show configs
+    file::show_contents(
+        path.file_name().map_or("", |v| v.to_str().unwrap_or("")),
+        &contents,
+        true,
+    )
+    .with_context(|| format!("try to show file `{}`", path.display()))?;
 
     // Success
     Ok(())
@@ -12,7 +12,7 @@ use crate::{
 pub async fn update_geo() -> Result<()> {
     // Call API
     Config::get_instance()
-        .get_api()
+        .get_api()?
         .upgrade_geo()
         .await
         .with_context(|| "try to call api")?;
@@ -24,7 +24,7 @@ pub async fn update_geo() -> Result<()> {
 pub async fn update_group() -> Result<()> {
     // Get groups
     let groups = Config::get_instance()
-        .get_api()
+        .get_api()?
         .get_groups()
         .await
         .with_context(|| "try to call api")?
@@ -116,7 +116,7 @@ pub async fn update_group() -> Result<()> {
 
     // Update
     Config::get_instance()
-        .get_api()
+        .get_api()?
         .update_proxy(&group.name, proxy)
         .await?;
 
@@ -127,7 +127,7 @@ pub async fn update_group() -> Result<()> {
 pub async fn restart() -> Result<()> {
     // Call API
     Config::get_instance()
-        .get_api()
+        .get_api()?
         .restart()
         .await
         .with_context(|| "try to call api")?;
@@ -0,0 +1,149 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use chrono::{Local, Utc};
+
+use crate::{
+    cmd::profile,
+    data::{config::Config, profile::Metas, state::State},
+};
+
+/// Max sleep between two checks, so that profile changes are picked up
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// First retry delay after a failed update, doubled on each failure
+const RETRY_BASE: Duration = Duration::from_secs(60);
+
+/// Backoff state of a failing profile
+struct Backoff {
+    failures: u32,
+    retry_at: Instant,
+}
+
+pub async fn daemon() -> Result<()> {
+    log("Daemon started".into());
+
+    let mut backoffs = HashMap::<String, Backoff>::new();
+    loop {
+        // Collect due profiles
+        let now = Utc::now().timestamp();
+        let due = {
+            let mut metas = Metas::get_instance().lock().unwrap();
+            metas
+                .reload()
+                .with_context(|| "try to reload profile metadatas")?;
+
+            metas
+                .values()
+                .filter(|v| v.remote)
+                .filter_map(|v| {
+                    let interval = v
+                        .try_get_conf()
+                        .ok()
+                        .and_then(|c| c.update_interval)
+                        .or(v.update_interval)?;
+                    let next = v.updated_at.unwrap_or(0) + interval as i64 * 60;
+                    (next <= now).then(|| v.uuid.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Update due profiles
+        for uuid in due {
+            if backoffs
+                .get(&uuid)
+                .is_some_and(|v| v.retry_at > Instant::now())
+            {
+                continue;
+            }
+
+            match update(&uuid).await {
+                Ok(()) => {
+                    backoffs.remove(&uuid);
+                }
+                Err(err) => {
+                    let failures = backoffs.get(&uuid).map_or(0, |v| v.failures) + 1;
+                    let delay = RETRY_BASE * 2u32.pow(failures.min(6) - 1);
+                    log(console::style(format!(
+                        "Profile with UUID `{}` fail to update, retry in {}min: {:#}",
+                        uuid,
+                        delay.as_secs() / 60,
+                        err
+                    ))
+                    .red()
+                    .to_string());
+                    backoffs.insert(
+                        uuid,
+                        Backoff {
+                            failures,
+                            retry_at: Instant::now() + delay,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Wait
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn update(uuid: &str) -> Result<()> {
+    // Update profile
+    let (meta, changed) = profile::update_one(uuid).await?;
+    if !changed {
+        log(console::style(format!(
+            "Profile `{}` with UUID `{}` unchanged",
+            meta.name, meta.uuid
+        ))
+        .bright()
+        .black()
+        .to_string());
+        return Ok(());
+    }
+    log(console::style(format!(
+        "Profile `{}` with UUID `{}` updated",
+        meta.name, meta.uuid
+    ))
+    .green()
+    .to_string());
+
+    // Re-activate if the active profile's data changed, including composite ones
+    let state = State::load().with_context(|| "try to load state")?;
+    let active = match &state.active {
+        Some(uuid) => Metas::get_instance().lock().unwrap().get(uuid).cloned(),
+        None => None,
+    };
+    let Some(meta) = active else {
+        return Ok(());
+    };
+    let conf = meta
+        .try_get_conf()
+        .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+    if state
+        .is_data_changed(&conf)
+        .with_context(|| format!("try to check profile data by UUID `{}`", meta.uuid))?
+    {
+        let cfg = Config::get_instance();
+        let output = conf
+            .activate(false, cfg.activation_method, cfg.localize_providers)
+            .await
+            .with_context(|| format!("try to activate profile by UUID `{}`", meta.uuid))?;
+        profile::record_activation(&meta, &conf, &output)?;
+        log(console::style(format!(
+            "Profile `{}` with UUID `{}` activated",
+            meta.name, meta.uuid
+        ))
+        .green()
+        .to_string());
+    }
+
+    Ok(())
+}
+
+fn log(msg: String) {
+    println!("[{}] {}", Local::now().format("%Y-%m-%d %H:%M:%S"), msg);
+}
@@ -1,5 +1,6 @@
 pub mod config;
 pub mod ctrl;
+pub mod daemon;
 pub mod profile;
 pub mod show;
 pub mod status;
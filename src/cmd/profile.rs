@@ -1,41 +1,91 @@
-use std::fs;
+use std::{fs, path::PathBuf, time::Duration};
 
-use anyhow::{bail, Context, Result};
-use chrono::Utc;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Local;
+use regex::{NoExpand, Regex};
+use serde::Serialize;
 use tokio::task::JoinSet;
 
 use crate::{
+    arg::profile::ConfigArgs,
     data::{
-        config::Config,
-        profile::{Config as ProfileConfig, ConfigType as ProfileConfigType, Meta, Metas},
+        config::{Config, ConfigActivationMethod},
+        profile::{
+            load_verge_profiles, refresh_providers, validate_rendered, Bundle, BundleProfile,
+            Config as ProfileConfig, ConfigType as ProfileConfigType, History, Meta, Metas,
+            VergeProfile, DEFAULT_USER_AGENT,
+        },
+        state::State,
     },
     utils::{
-        self, file, path, prompt,
+        self, diff, file,
+        output::{self, OutputFormat},
+        path, prompt,
         result::{normal, success},
     },
 };
 
-const DEFAULT_CONFIG_TEMPLATE: &'static str = include_str!("../includes/default_profile.yaml");
+const DEFAULT_CONFIG_TEMPLATE: &str = include_str!("../includes/default_profile.yaml");
 
-pub async fn activate(uuid_or_name: String) -> Result<()> {
-    // Get profile metadatas
-    let metas = Metas::get_instance().lock().unwrap();
+/// Polling interval of watching profile data file
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+pub async fn activate(
+    uuid_or_name: String,
+    watch: bool,
+    dry_run: bool,
+    force: bool,
+    method: Option<ConfigActivationMethod>,
+    localize: bool,
+) -> Result<()> {
+    let cfg = Config::get_instance();
+    let method = method.unwrap_or(cfg.activation_method);
+    let localize = localize || cfg.localize_providers;
 
     // Get profile metadata
-    let meta = metas
+    let meta = Metas::get_instance()
+        .lock()
+        .unwrap()
         .try_get_meta(&uuid_or_name)
-        .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?;
+        .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?
+        .clone();
 
     // Get profile config
     let conf = meta
         .try_get_conf()
         .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
 
+    // Show differences only
+    if dry_run {
+        return show_render_diff(&conf);
+    }
+
     // Activate profile
-    conf.activate()
+    let output = conf
+        .activate(force, method, localize)
         .await
         .with_context(|| format!("try to activate profile by UUID `{}`", meta.uuid))?;
 
+    // Update state
+    record_activation(&meta, &conf, &output)?;
+
+    // Watch data file
+    if watch {
+        println!(
+            "{}",
+            console::style(format!(
+                "Profile `{}` with UUID `{}` activated, watching `{}` for changes",
+                meta.name,
+                meta.uuid,
+                conf.get_data_path().display()
+            ))
+            .bold()
+            .bright()
+            .green()
+        );
+        return watch_data(&meta, &conf, force, method, localize).await;
+    }
+
     // Success
     success!(
         "Profile `{}` with UUID `{}` activated",
@@ -44,6 +94,192 @@ pub async fn activate(uuid_or_name: String) -> Result<()> {
     )
 }
 
+/// Re-activate the profile whenever its data file is modified
+async fn watch_data(
+    meta: &Meta,
+    conf: &ProfileConfig,
+    force: bool,
+    method: ConfigActivationMethod,
+    localize: bool,
+) -> Result<()> {
+    let path = conf.get_data_path();
+    let get_modified = || fs::metadata(&path).and_then(|v| v.modified()).ok();
+
+    let mut last = get_modified();
+    loop {
+        tokio::time::sleep(WATCH_INTERVAL).await;
+
+        // Check modified time, wait a moment to let writers finish
+        let modified = get_modified();
+        if modified.is_none() || modified == last {
+            continue;
+        }
+        tokio::time::sleep(WATCH_INTERVAL).await;
+        last = get_modified();
+
+        // Re-activate
+        let r = match conf.activate(force, method, localize).await {
+            Ok(output) => record_activation(meta, conf, &output),
+            Err(err) => Err(err),
+        };
+        match r {
+            Ok(()) => println!(
+                "{}",
+                console::style(format!(
+                    "[{}] Profile `{}` re-activated",
+                    Local::now().format("%H:%M:%S"),
+                    conf.name
+                ))
+                .green()
+            ),
+            Err(err) => println!(
+                "{}",
+                console::style(format!(
+                    "[{}] Profile `{}` fail to re-activate: {:#}",
+                    Local::now().format("%H:%M:%S"),
+                    conf.name,
+                    err
+                ))
+                .red()
+            ),
+        }
+    }
+}
+
+pub fn activations(format: OutputFormat) -> Result<()> {
+    let state = State::load().with_context(|| "try to load state")?;
+
+    // Print serialized
+    if format != OutputFormat::Table {
+        return output::print_serialized(&state.activations, format);
+    }
+    if state.activations.is_empty() {
+        return normal!("No activations found");
+    }
+
+    // Print list
+    println!(
+        "{:19}    {:^8}    {:16}    {}",
+        console::style("Activated At").bold().bright().blue(),
+        console::style("UUID").bold().bright().blue(),
+        console::style("Name").bold().bright().blue(),
+        console::style("Output Hash").bold().bright().blue(),
+    );
+    for v in state.activations.iter().rev() {
+        println!(
+            "{:19}    {:^8}    {:16}    {}",
+            v.get_styled_activated_at(),
+            v.uuid,
+            v.name,
+            console::style(v.output_hash.get(..12).unwrap_or(&v.output_hash))
+                .bright()
+                .black(),
+        );
+    }
+
+    // Success
+    Ok(())
+}
+
+pub fn current(format: OutputFormat) -> Result<()> {
+    let cfg = Config::get_instance();
+    let state = State::load().with_context(|| "try to load state")?;
+
+    // Get active profile
+    let uuid = match &state.active {
+        Some(v) => v,
+        None if format == OutputFormat::Table => return normal!("No profile activated"),
+        None => return output::print_serialized(&None::<()>, format),
+    };
+    let meta = Metas::get_instance().lock().unwrap().get(uuid).cloned();
+
+    // Compare hashes
+    let output_modified = match fs::read_to_string(&cfg.mihomo_path) {
+        Ok(v) => state.output_hash.as_deref() != Some(utils::hash(v).as_str()),
+        Err(_) => true,
+    };
+    let data_changed = match meta.as_ref().map(|v| v.try_get_conf()) {
+        Some(Ok(conf)) => state
+            .is_data_changed(&conf)
+            .with_context(|| format!("try to check profile data by UUID `{}`", uuid))?,
+        _ => false,
+    };
+
+    // Print serialized
+    if format != OutputFormat::Table {
+        #[derive(Serialize)]
+        struct Current<'a> {
+            uuid: &'a str,
+            name: Option<&'a str>,
+            activated_at: Option<i64>,
+            output_hash: Option<&'a str>,
+            data_hash: Option<&'a str>,
+            output_modified: bool,
+            data_changed: bool,
+        }
+        return output::print_serialized(
+            &Current {
+                uuid,
+                name: meta.as_ref().map(|v| v.name.as_str()),
+                activated_at: state.activated_at,
+                output_hash: state.output_hash.as_deref(),
+                data_hash: state.data_hash.as_deref(),
+                output_modified,
+                data_changed,
+            },
+            format,
+        );
+    }
+
+    // Print info
+    let na = || console::style("N/A".to_owned()).bright().black();
+    let flag = |v: bool, yes: &str, no: &str| {
+        if v {
+            console::style(yes.to_owned()).yellow()
+        } else {
+            console::style(no.to_owned()).green()
+        }
+    };
+    let rows = [
+        ("UUID", console::style(uuid.clone())),
+        (
+            "Name",
+            meta.as_ref().map_or(
+                console::style("(deleted)".to_owned()).bright().black(),
+                |v| console::style(v.name.clone()),
+            ),
+        ),
+        (
+            "Activated At",
+            state
+                .activations
+                .last()
+                .map_or(na(), |v| console::style(v.get_styled_activated_at())),
+        ),
+        (
+            "Output Hash",
+            state
+                .output_hash
+                .as_ref()
+                .map_or(na(), |v| console::style(v.clone())),
+        ),
+        (
+            "Mihomo Config",
+            flag(output_modified, "modified since activation", "unchanged"),
+        ),
+        (
+            "Profile Data",
+            flag(data_changed, "changed since activation", "unchanged"),
+        ),
+    ];
+    for (k, v) in rows {
+        println!("{:>13}    {}", console::style(k).bold().bright().blue(), v);
+    }
+
+    // Success
+    Ok(())
+}
+
 pub fn delete(uuid_or_name: String) -> Result<()> {
     // Get profile metadata map
     let mut metas = Metas::get_instance().lock().unwrap();
@@ -80,13 +316,91 @@ pub fn delete(uuid_or_name: String) -> Result<()> {
             .with_context(|| format!("try to delete file `{}`", path.display()))?;
     }
 
+    // Delete history
+    History::new(&meta.uuid)
+        .remove()
+        .with_context(|| "try to delete profile history")?;
+
     // Success
     success!("Profile `{}` with UUID `{}` deleted", meta.name, meta.uuid)
 }
 
-pub fn edit_conf(uuid_or_name: String) -> Result<()> {
-    let cfg = Config::get_instance();
+pub fn diff(uuid_or_name: String, old: Option<usize>, new: Option<usize>) -> Result<()> {
+    let metas = Metas::get_instance().lock().unwrap();
+
+    // Get profile metadata
+    let meta = metas
+        .try_get_meta(&uuid_or_name)
+        .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?;
+
+    // Get old revision
+    let history = History::new(&meta.uuid);
+    let old = match old {
+        Some(rev) => history.get(rev)?,
+        None => {
+            let revs = history
+                .list()
+                .with_context(|| "try to list profile history")?;
+            let idx = revs.len().saturating_sub(2);
+            revs.into_iter()
+                .nth(idx)
+                .ok_or(anyhow!("no history revisions found"))?
+        }
+    };
+    let old_name = format!("rev {}", old.rev);
+    let old_conf = old.read_conf()?.unwrap_or_default();
+    let old_data = old.read_data()?.unwrap_or_default();
+
+    // Get new revision or current files
+    let (new_name, new_conf, new_data) = match new {
+        Some(rev) => {
+            let new = history.get(rev)?;
+            (
+                format!("rev {}", new.rev),
+                new.read_conf()?.unwrap_or_default(),
+                new.read_data()?.unwrap_or_default(),
+            )
+        }
+        None => {
+            let conf = path::get_profile_conf_dir().join(format!("{}.yaml", meta.uuid));
+            let data = path::get_profile_data_dir().join(format!("{}.yaml", meta.uuid));
+            (
+                "current".to_owned(),
+                fs::read_to_string(&conf).unwrap_or_default(),
+                fs::read_to_string(&data).unwrap_or_default(),
+            )
+        }
+    };
+
+    // Redact credentials
+    let old_conf = ProfileConfig::redact_contents(&old_conf);
+    let new_conf = ProfileConfig::redact_contents(&new_conf);
+
+    // Build diff
+    let mut contents = diff::unified(
+        &old_conf,
+        &new_conf,
+        format!("{} configs", old_name),
+        format!("{} configs", new_name),
+    );
+    contents.push_str(&diff::unified(
+        &old_data,
+        &new_data,
+        format!("{} data", old_name),
+        format!("{} data", new_name),
+    ));
+    if contents.trim().is_empty() {
+        return normal!("No differences");
+    }
+
+    // Show contents
+    file::show_contents("diff", &contents, false).with_context(|| "fail to show diff")?;
 
+    // Success
+    Ok(())
+}
+
+pub fn edit_conf(uuid_or_name: String, args: ConfigArgs) -> Result<()> {
     // Get profile metadata map
     let mut metas = Metas::get_instance().lock().unwrap();
 
@@ -100,10 +414,9 @@ pub fn edit_conf(uuid_or_name: String) -> Result<()> {
     let path = path::get_profile_conf_dir().join(format!("{}.yaml", meta.uuid));
     let contents = fs::read_to_string(&path)
         .with_context(|| format!("try to read file `{}`", path.display()))?;
-    let contents = file::edit_temp_file(".yaml", Some(&cfg.editor), Some(&contents))
-        .with_context(|| "try to edit temporary contents")?;
-    let value = serde_yaml::from_str::<ProfileConfig>(&contents)
-        .with_context(|| "try to parse temporary contents")?;
+    let contents = get_conf_contents(&args, &contents)?;
+    let value =
+        ProfileConfig::from_str(&contents).with_context(|| "try to parse temporary contents")?;
     value
         .verify()
         .with_context(|| "try to verify temporary contents")?;
@@ -123,22 +436,29 @@ pub fn edit_conf(uuid_or_name: String) -> Result<()> {
         meta.uuid.clone(),
         Meta {
             name: value.name.clone(),
-            remote: if let ProfileConfigType::Local = value.r#type {
-                false
-            } else {
-                true
-            },
+            remote: value.r#type == ProfileConfigType::Remote,
             expired_at: None,
             total_bytes: None,
+            etag: None,
+            last_modified: None,
             ..old
         },
     );
     metas.flush().with_context(|| "try to flush MetadataMap")?;
 
     // Update config file
-    fs::write(&path, contents)
+    let history = History::new(&meta.uuid);
+    history
+        .snapshot_initial()
+        .with_context(|| "try to snapshot profile history")?;
+    file::write_private(&path, contents)
         .with_context(|| format!("try to write file `{}`", path.display()))?;
 
+    // Snapshot history
+    history
+        .snapshot("edit-configs")
+        .with_context(|| "try to snapshot profile history")?;
+
     // Success
     success!(
         "Profile configurations `{}` with UUID `{}` edited",
@@ -147,7 +467,7 @@ pub fn edit_conf(uuid_or_name: String) -> Result<()> {
     )
 }
 
-pub fn edit_data(uuid_or_name: String) -> Result<()> {
+pub fn edit_data(uuid_or_name: String, from_file: Option<PathBuf>) -> Result<()> {
     let cfg = Config::get_instance();
 
     // Get profile metadata map
@@ -158,6 +478,20 @@ pub fn edit_data(uuid_or_name: String) -> Result<()> {
         .try_get_meta(&uuid_or_name)
         .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?;
 
+    // Get profile config
+    let conf = meta
+        .try_get_conf()
+        .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+    if conf.r#type == ProfileConfigType::File {
+        bail!(
+            "data of file profile is tracked at `{}`, please edit it directly",
+            conf.get_data_path().display()
+        );
+    }
+    if conf.r#type == ProfileConfigType::Composite {
+        bail!("data of composite profile is composed from its member profiles");
+    }
+
     // Edit profile data
     let path = path::get_profile_data_dir().join(format!("{}.yaml", meta.uuid));
     let contents = if path.is_file() {
@@ -166,8 +500,12 @@ pub fn edit_data(uuid_or_name: String) -> Result<()> {
     } else {
         String::new()
     };
-    let contents = file::edit_temp_file(".yaml", Some(&cfg.editor), Some(&contents))
-        .with_context(|| "try to edit temporary contents")?;
+    let contents = match from_file {
+        Some(path) => file::read_input(&path)
+            .with_context(|| format!("try to read file `{}`", path.display()))?,
+        None => file::edit_temp_file(".yaml", Some(&cfg.editor), Some(&contents))
+            .with_context(|| "try to edit temporary contents")?,
+    };
 
     // Confirm to save
     let prompt = format!(
@@ -179,9 +517,18 @@ pub fn edit_data(uuid_or_name: String) -> Result<()> {
     }
 
     // Update data file
-    fs::write(&path, contents)
+    let history = History::new(&meta.uuid);
+    history
+        .snapshot_initial()
+        .with_context(|| "try to snapshot profile history")?;
+    file::write_private(&path, contents)
         .with_context(|| format!("try to write file `{}`", path.display()))?;
 
+    // Snapshot history
+    history
+        .snapshot("edit-data")
+        .with_context(|| "try to snapshot profile history")?;
+
     // Success
     success!(
         "Profile data `{}` with UUID `{}` edited",
@@ -190,11 +537,294 @@ pub fn edit_data(uuid_or_name: String) -> Result<()> {
     )
 }
 
-pub fn list() -> Result<()> {
+pub fn export(uuid_or_names: Vec<String>, output: PathBuf, strip_secrets: bool) -> Result<()> {
+    // Get profile metadatas
+    let selected = {
+        let metas = Metas::get_instance().lock().unwrap();
+        let mut selected = Vec::<Meta>::new();
+        for uuid_or_name in &uuid_or_names {
+            let meta = metas
+                .try_get_meta(uuid_or_name)
+                .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?;
+            if !selected.iter().any(|v| v.uuid == meta.uuid) {
+                selected.push(meta.clone());
+            }
+        }
+        selected
+    };
+
+    // Pack bundle
+    let bundle =
+        Bundle::pack(&selected, strip_secrets).with_context(|| "try to pack profile bundle")?;
+    let contents = serde_yaml::to_string(&bundle)?;
+
+    // Write bundle
+    if output.as_os_str() == "-" {
+        print!("{}", contents);
+        return Ok(());
+    }
+    file::write_private(&output, contents)
+        .with_context(|| format!("try to write file `{}`", output.display()))?;
+
+    // Success
+    success!(
+        "{} profiles exported to `{}`",
+        bundle.profiles.len(),
+        output.display()
+    )
+}
+
+pub fn history(uuid_or_name: String) -> Result<()> {
     let metas = Metas::get_instance().lock().unwrap();
 
+    // Get profile metadata
+    let meta = metas
+        .try_get_meta(&uuid_or_name)
+        .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?;
+
+    // Get revisions
+    let revs = History::new(&meta.uuid)
+        .list()
+        .with_context(|| "try to list profile history")?;
+    if revs.is_empty() {
+        return normal!("No history revisions found");
+    }
+
+    // Print list
+    println!(
+        "{:>4}    {:19}    {}",
+        console::style("Rev").bold().bright().blue(),
+        console::style("Created At").bold().bright().blue(),
+        console::style("Action").bold().bright().blue(),
+    );
+    for rev in revs.iter().rev() {
+        println!(
+            "{:>4}    {:19}    {}",
+            rev.rev,
+            rev.get_styled_created_at(),
+            console::style(&rev.action).bright().yellow(),
+        );
+    }
+
+    // Success
+    Ok(())
+}
+
+pub fn import(bundle: PathBuf) -> Result<()> {
+    // Read bundle
+    let contents = file::read_input(&bundle)
+        .with_context(|| format!("try to read file `{}`", bundle.display()))?;
+    let bundle = Bundle::from_str(contents).with_context(|| "try to parse profile bundle")?;
+
+    let mut metas = Metas::get_instance().lock().unwrap();
+
+    // Skip profiles with existing names
+    let profiles = bundle
+        .profiles
+        .into_iter()
+        .filter(|v| {
+            let exists = metas.values().any(|m| m.name == v.meta.name);
+            if exists {
+                println!(
+                    "{}",
+                    console::style(format!("Profile `{}` already exists, skipped", v.meta.name))
+                        .bright()
+                        .black()
+                );
+            }
+            !exists
+        })
+        .collect::<Vec<_>>();
+    if profiles.is_empty() {
+        return normal!("No profiles to import");
+    }
+
+    // Verify configs
+    for v in &profiles {
+        ProfileConfig::from_str(&v.config)
+            .with_context(|| format!("try to parse config of profile `{}`", v.meta.name))?
+            .verify()
+            .with_context(|| format!("try to verify config of profile `{}`", v.meta.name))?;
+    }
+
+    // Confirm to import
+    for v in &profiles {
+        println!(
+            "{}{}",
+            console::style(&v.meta.name).bold().bright().blue(),
+            if v.data.is_none() {
+                console::style(" (no data)").bright().black().to_string()
+            } else {
+                String::new()
+            }
+        );
+    }
+    let prompt = format!("Are you sure to import these {} profiles?", profiles.len());
+    if !prompt::confirm(&prompt).with_context(|| "try to show confirm prompt")? {
+        return normal!("Nothing changed");
+    }
+
+    // Import profiles
+    let count = profiles.len();
+    for BundleProfile {
+        mut meta,
+        config,
+        data,
+    } in profiles
+    {
+        let uuid = utils::gen_uuid();
+        meta.uuid = uuid.clone();
+
+        // Write config file
+        let path = path::get_profile_conf_dir().join(format!("{}.yaml", uuid));
+        file::write_private(&path, config)
+            .with_context(|| format!("try to write file `{}`", path.display()))?;
+
+        // Write data file
+        if let Some(data) = data {
+            let path = path::get_profile_data_dir().join(format!("{}.yaml", uuid));
+            file::write_private(&path, data)
+                .with_context(|| format!("try to write file `{}`", path.display()))?;
+        }
+
+        // Snapshot history
+        History::new(&uuid)
+            .snapshot("import")
+            .with_context(|| "try to snapshot profile history")?;
+
+        metas.insert(uuid, meta);
+    }
+    metas.flush().with_context(|| "try to flush MetadataMap")?;
+
+    // Success
+    success!("{} profiles imported", count)
+}
+
+pub fn import_verge(dir: PathBuf) -> Result<()> {
+    let mut metas = Metas::get_instance().lock().unwrap();
+
+    // Load profiles
+    let profiles = load_verge_profiles(&dir)
+        .with_context(|| format!("try to load Clash Verge profiles from `{}`", dir.display()))?;
+
+    // Skip profiles with existing names
+    let profiles = profiles
+        .into_iter()
+        .filter(|v| {
+            let exists = metas.values().any(|m| m.name == v.conf.name);
+            if exists {
+                println!(
+                    "{}",
+                    console::style(format!("Profile `{}` already exists, skipped", v.conf.name))
+                        .bright()
+                        .black()
+                );
+            }
+            !exists
+        })
+        .collect::<Vec<_>>();
+    if profiles.is_empty() {
+        return normal!("No profiles to import");
+    }
+
+    // Confirm to import
+    for v in &profiles {
+        println!(
+            "{} ({})",
+            console::style(&v.conf.name).bold().bright().blue(),
+            console::style(v.conf.r#type.to_string()).bright().yellow()
+        );
+    }
+    let prompt = format!("Are you sure to import these {} profiles?", profiles.len());
+    if !prompt::confirm(&prompt).with_context(|| "try to show confirm prompt")? {
+        return normal!("Nothing changed");
+    }
+
+    // Import profiles
+    let count = profiles.len();
+    for VergeProfile {
+        mut conf,
+        mut meta,
+        data,
+    } in profiles
+    {
+        let uuid = utils::gen_uuid();
+        conf.uuid = uuid.clone();
+        meta.uuid = uuid.clone();
+
+        // Write config file
+        let path = path::get_profile_conf_dir().join(format!("{}.yaml", uuid));
+        file::write_private(&path, serde_yaml::to_string(&conf)?)
+            .with_context(|| format!("try to write file `{}`", path.display()))?;
+
+        // Write data file
+        if let Some(data) = data {
+            let path = path::get_profile_data_dir().join(format!("{}.yaml", uuid));
+            file::write_private(&path, data)
+                .with_context(|| format!("try to write file `{}`", path.display()))?;
+        }
+
+        // Snapshot history
+        History::new(&uuid)
+            .snapshot("import")
+            .with_context(|| "try to snapshot profile history")?;
+
+        metas.insert(uuid, meta);
+    }
+    metas.flush().with_context(|| "try to flush MetadataMap")?;
+
+    // Success
+    success!("{} profiles imported", count)
+}
+
+pub fn info(uuid_or_name: String) -> Result<()> {
+    let metas = Metas::get_instance().lock().unwrap();
+
+    // Get profile metadata
+    let meta = metas
+        .try_get_meta(&uuid_or_name)
+        .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?;
+
+    // Get profile config
+    let conf = meta
+        .try_get_conf()
+        .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+
+    // Print info, with credentials redacted
+    let na = || console::style("N/A".to_owned()).bright().black();
+    let size = |v: Option<usize>| v.map_or(na(), |v| console::style(utils::get_size_str(v)));
+    let text = |v: Option<&String>| v.map_or(na(), |v| console::style(v.to_owned()));
+    let interval = |v: Option<u64>| v.map_or(na(), |v| console::style(format!("{}min", v)));
+    let rows = [
+        ("UUID", console::style(meta.uuid.clone())),
+        ("Name", console::style(meta.name.clone())),
+        ("Type", console::style(conf.r#type.to_string())),
+        ("URL", text(conf.get_redacted_url().as_ref())),
+        ("Updated At", meta.get_styled_duration()),
+        ("Expired At", meta.get_styled_expired_at()),
+        ("Upload", size(meta.upload_bytes)),
+        ("Download", size(meta.download_bytes)),
+        ("Total", size(meta.total_bytes)),
+        ("Usage", meta.get_styled_usage()),
+        ("Update Interval", interval(conf.update_interval)),
+        ("Provider Interval", interval(meta.update_interval)),
+        ("Web Page", text(meta.get_redacted_web_page_url().as_ref())),
+        ("File Name", text(meta.file_name.as_ref())),
+    ];
+    for (k, v) in rows {
+        println!("{:>17}    {}", console::style(k).bold().bright().blue(), v);
+    }
+
+    // Success
+    Ok(())
+}
+
+pub fn list(format: OutputFormat) -> Result<()> {
+    let metas = Metas::get_instance().lock().unwrap();
+    let state = State::load().with_context(|| "try to load state")?;
+
     // If no profile
-    if metas.len() == 0 {
+    if metas.is_empty() && format == OutputFormat::Table {
         return normal!("No profiles found");
     }
 
@@ -207,10 +837,31 @@ pub fn list() -> Result<()> {
             a.1.name.cmp(&b.1.name)
         }
     });
+    if format != OutputFormat::Table {
+        #[derive(Serialize)]
+        struct Entry<'a> {
+            uuid: &'a str,
+            active: bool,
+            #[serde(flatten)]
+            meta: Meta,
+        }
+        let entries = kv
+            .into_iter()
+            .map(|(uuid, meta)| Entry {
+                uuid,
+                active: state.active.as_ref() == Some(uuid),
+                meta: Meta {
+                    web_page_url: meta.get_redacted_web_page_url(),
+                    ..meta.clone()
+                },
+            })
+            .collect::<Vec<_>>();
+        return output::print_serialized(&entries, format);
+    }
 
     // Print list
     println!(
-        "{:^8}    {:16}    {:^6}    {:^10}    {:^10}    {}",
+        "  {:^8}    {:16}    {:^6}    {:^10}    {:^10}    {}",
         console::style("UUID").bold().bright().blue(),
         console::style("Name").bold().bright().blue(),
         console::style("Remote").bold().bright().blue(),
@@ -220,7 +871,12 @@ pub fn list() -> Result<()> {
     );
     for (k, v) in kv {
         println!(
-            "{:^8}    {:16}    {:^6}    {:^10}    {:^10}    {}",
+            "{} {:^8}    {:16}    {:^6}    {:^10}    {:^10}    {}",
+            if state.active.as_ref() == Some(k) {
+                console::style("*").bold().bright().green()
+            } else {
+                console::style(" ")
+            },
             k,
             v.get_styled_name(),
             v.get_styled_remote(),
@@ -234,125 +890,266 @@ pub fn list() -> Result<()> {
     Ok(())
 }
 
-pub fn new() -> Result<()> {
-    let cfg = Config::get_instance();
-    let mut metas = Metas::get_instance().lock().unwrap();
-
+pub async fn new(args: ConfigArgs) -> Result<()> {
     // Edit temporary file
-    let contents = file::edit_temp_file(
-        ".yaml",
-        Some(&cfg.editor),
-        Some(DEFAULT_CONFIG_TEMPLATE.replace("<CARGO_PKG_VERSION>", env!("CARGO_PKG_VERSION"))),
-    )
-    .with_context(|| "try to edit temporary contents")?;
-    let value = serde_yaml::from_str::<ProfileConfig>(&contents)
-        .with_context(|| "try to parse temporary contents")?;
+    let mut contents = get_conf_contents(
+        &args,
+        DEFAULT_CONFIG_TEMPLATE.replace("<DEFAULT_USER_AGENT>", DEFAULT_USER_AGENT),
+    )?;
+    let mut value =
+        ProfileConfig::from_str(&contents).with_context(|| "try to parse temporary contents")?;
+
+    // Fill default user agent for remote profile given on command line
+    if args.is_set() && value.r#type == ProfileConfigType::Remote && value.user_agent.is_none() {
+        let user_agent = DEFAULT_USER_AGENT.to_owned();
+        contents = set_conf_field(&contents, "user_agent", &user_agent)
+            .with_context(|| "try to set profile user agent in temporary contents")?;
+        value.user_agent = Some(user_agent);
+    }
     value
         .verify()
         .with_context(|| "try to verify the temporary contents")?;
 
+    // Prefetch remote profile
+    let uuid = utils::gen_uuid();
+    value.uuid = uuid.clone();
+    let mut meta = Meta {
+        uuid: uuid.clone(),
+        name: value.name.clone(),
+        remote: value.r#type == ProfileConfigType::Remote,
+        ..Default::default()
+    };
+    if meta.remote {
+        match value.fetch(&meta).await {
+            Ok(r) => meta.apply_fetch_result(r),
+            Err(err) => println!(
+                "{}",
+                console::style(format!(
+                    "Fail to fetch profile data, you can update it later: {:#}",
+                    err
+                ))
+                .yellow()
+            ),
+        }
+    }
+
+    // Ask to use provider suggested name, unless name is given on command line
+    if let Some(name) = meta
+        .get_suggested_name()
+        .filter(|v| args.name.is_none() && *v != value.name)
+    {
+        let prompt = format!(
+            "Do you want to use the provider suggested name `{}` instead of `{}`?",
+            name, value.name
+        );
+        if prompt::confirm(&prompt).with_context(|| "try to show confirm prompt")? {
+            contents = set_conf_field(&contents, "name", &name)
+                .with_context(|| "try to set profile name in temporary contents")?;
+            value.name = name.clone();
+            meta.name = name;
+        }
+    }
+
     // Confirm to create
     let prompt = format!("Are you sure to create the new profile `{}`?", value.name);
     if !prompt::confirm(&prompt).with_context(|| "try to show confirm prompt")? {
+        let path = path::get_profile_data_dir().join(format!("{}.yaml", uuid));
+        if path.is_file() {
+            fs::remove_file(&path)
+                .with_context(|| format!("try to delete file `{}`", path.display()))?;
+        }
         return normal!("Nothing changed");
     }
 
     // Update metadata
-    let uuid = utils::gen_uuid();
-    metas.insert(
-        uuid.clone(),
-        Meta {
-            uuid: uuid.clone(),
-            name: value.name.clone(),
-            remote: if let ProfileConfigType::Local = value.r#type {
-                false
-            } else {
-                true
-            },
-            updated_at: None,
-            expired_at: None,
-            used_bytes: None,
-            total_bytes: None,
-        },
-    );
+    let mut metas = Metas::get_instance().lock().unwrap();
+    metas.insert(uuid.clone(), meta);
     metas.flush().with_context(|| "try to flush MetadataMap")?;
 
     // Update config file
     let path = path::get_profile_conf_dir().join(format!("{}.yaml", uuid));
-    fs::write(&path, contents)
+    file::write_private(&path, contents)
         .with_context(|| format!("try to write file `{}`", path.display()))?;
 
+    // Snapshot history
+    History::new(&uuid)
+        .snapshot("create")
+        .with_context(|| "try to snapshot profile history")?;
+
     // Success
     success!("New profile `{}` with UUID `{}` added", value.name, uuid)
 }
 
-pub async fn update(uuid_or_name: Option<String>) -> Result<()> {
+pub fn render(uuid_or_name: String, output: Option<PathBuf>) -> Result<()> {
+    // Get profile metadata
+    let meta = Metas::get_instance()
+        .lock()
+        .unwrap()
+        .try_get_meta(&uuid_or_name)
+        .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?
+        .clone();
+
+    // Get profile config
+    let conf = meta
+        .try_get_conf()
+        .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+
+    // Render config
+    let contents = conf
+        .render()
+        .with_context(|| format!("try to render profile by UUID `{}`", meta.uuid))?;
+
+    // Show or write contents
+    match output {
+        None => file::show_contents("rendered.yaml", &contents, true)
+            .with_context(|| "try to show rendered config"),
+        Some(path) if path.as_os_str() == "-" => {
+            print!("{}", contents);
+            Ok(())
+        }
+        Some(path) => {
+            file::write_atomic(&path, contents)
+                .with_context(|| format!("try to write file `{}`", path.display()))?;
+            success!(
+                "Profile `{}` with UUID `{}` rendered to `{}`",
+                meta.name,
+                meta.uuid,
+                path.display()
+            )
+        }
+    }
+}
+
+pub fn rollback(uuid_or_name: String, rev: usize) -> Result<()> {
+    // Get profile metadata map
     let mut metas = Metas::get_instance().lock().unwrap();
 
+    // Get profile metadata
+    let meta = metas
+        .try_get_meta(&uuid_or_name)
+        .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?
+        .clone();
+
+    // Check revision
+    let history = History::new(&meta.uuid);
+    let rev = history
+        .get(rev)
+        .with_context(|| "try to get profile history revision")?;
+    let contents = rev
+        .read_conf()?
+        .ok_or(anyhow!("revision `{}` has no configurations", rev.rev))?;
+    let value = ProfileConfig::from_str(&contents)
+        .with_context(|| format!("try to parse configurations of revision `{}`", rev.rev))?;
+
+    // Confirm to rollback
+    let prompt = format!(
+        "Are you sure to rollback the profile `{}` with UUID `{}` to revision `{}` ({})?",
+        meta.name,
+        meta.uuid,
+        rev.rev,
+        rev.get_styled_created_at()
+    );
+    if !prompt::confirm(&prompt).with_context(|| "try to show confirm prompt")? {
+        return normal!("Nothing changed");
+    }
+
+    // Restore files
+    history
+        .restore(rev.rev)
+        .with_context(|| format!("try to restore revision `{}`", rev.rev))?;
+
+    // Update metadata, cache validators no longer match the data
+    metas.insert(
+        meta.uuid.clone(),
+        Meta {
+            name: value.name.clone(),
+            remote: value.r#type == ProfileConfigType::Remote,
+            etag: None,
+            last_modified: None,
+            ..meta.clone()
+        },
+    );
+    metas.flush().with_context(|| "try to flush MetadataMap")?;
+
+    // Snapshot history
+    history
+        .snapshot(format!("rollback to {}", rev.rev))
+        .with_context(|| "try to snapshot profile history")?;
+
+    // Success
+    success!(
+        "Profile `{}` with UUID `{}` rolled back to revision `{}`",
+        value.name,
+        meta.uuid,
+        rev.rev
+    )
+}
+
+pub async fn update(uuid_or_name: Option<String>) -> Result<()> {
     // If update specific profile
     if let Some(uuid_or_name) = uuid_or_name {
-        // Get profile metadata
-        let mut meta = metas
-            .try_get_meta(&uuid_or_name)
-            .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?
-            .clone();
-
-        // Get profile config
-        let conf = meta
-            .try_get_conf()
-            .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
-
-        // Fetch
-        let (used, total, expired_at) = conf
-            .fetch()
-            .await
-            .with_context(|| format!("try to fetch profile data by UUID `{}`", meta.uuid))?;
-
-        // Update metadata
-        let name = meta.name.clone();
-        meta.used_bytes = used;
-        meta.total_bytes = total;
-        meta.updated_at = Some(Utc::now().timestamp());
-        meta.expired_at = expired_at;
-        metas.flush().with_context(|| "try to flush MetadataMap")?;
+        let (meta, changed) = update_one(&uuid_or_name).await?;
 
         // Success
-        success!("Profile `{}` with UUID `{}` updated", name, meta.uuid)
+        let r = if !changed {
+            normal!(
+                "Profile `{}` with UUID `{}` unchanged",
+                meta.name,
+                meta.uuid
+            )
+        } else {
+            success!("Profile `{}` with UUID `{}` updated", meta.name, meta.uuid)
+        };
+        refresh_provider_cache().await;
+        warn_if_active_changed()?;
+        r
     } else {
         // Create tasks
         let mut set = JoinSet::new();
-        for uuid in metas.iter().filter(|(_, v)| v.remote).map(|(k, _)| k) {
-            let uuid = uuid.to_owned();
-            let meta = metas
-                .try_get_meta(&uuid)
-                .with_context(|| format!("try to get profile metadata by `{}`", uuid))?
-                .clone();
-            let conf = meta
-                .try_get_conf()
-                .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
-            set.spawn(async move {
-                let r = conf.fetch().await;
-                match &r {
-                    Ok(_) => println!(
-                        "{}",
-                        console::style(format!(
-                            "Profile `{}` with UUID `{}` updated",
-                            conf.name, uuid
-                        ))
-                        .green()
-                    ),
-                    Err(err) => println!(
-                        "{}",
-                        console::style(format!(
-                            "Profile `{}` with UUID `{}` fail to update: {}",
-                            conf.name, uuid, err
-                        ))
-                        .red()
-                    ),
-                }
-
-                (uuid, r)
-            });
+        {
+            let metas = Metas::get_instance().lock().unwrap();
+            for meta in metas.values().filter(|v| v.remote) {
+                let meta = meta.clone();
+                let uuid = meta.uuid.clone();
+                let conf = meta.try_get_conf().with_context(|| {
+                    format!("try to get profile config by UUID `{}`", meta.uuid)
+                })?;
+                History::new(&uuid)
+                    .snapshot_initial()
+                    .with_context(|| "try to snapshot profile history")?;
+                set.spawn(async move {
+                    let r = conf.fetch(&meta).await;
+                    match &r {
+                        Ok(r) if r.unchanged => println!(
+                            "{}",
+                            console::style(format!(
+                                "Profile `{}` with UUID `{}` unchanged",
+                                conf.name, uuid
+                            ))
+                            .bright()
+                            .black()
+                        ),
+                        Ok(_) => println!(
+                            "{}",
+                            console::style(format!(
+                                "Profile `{}` with UUID `{}` updated",
+                                conf.name, uuid
+                            ))
+                            .green()
+                        ),
+                        Err(err) => println!(
+                            "{}",
+                            console::style(format!(
+                                "Profile `{}` with UUID `{}` fail to update: {}",
+                                conf.name, uuid, err
+                            ))
+                            .red()
+                        ),
+                    }
+
+                    (uuid, r)
+                });
+            }
         }
 
         // Execute tasks
@@ -363,20 +1160,222 @@ pub async fn update(uuid_or_name: Option<String>) -> Result<()> {
             .map(|(k, v)| (k, v.unwrap()));
 
         // Update metadata
-        for (uuid, (used, total, expired_at)) in rx {
-            let meta = metas.get_mut(&uuid).unwrap();
-            meta.used_bytes = used;
-            meta.total_bytes = total;
-            meta.updated_at = Some(Utc::now().timestamp());
-            meta.expired_at = expired_at;
+        let mut changed = Vec::new();
+        {
+            let mut metas = Metas::get_instance().lock().unwrap();
+            for (uuid, r) in rx {
+                if let Some(meta) = metas.get_mut(&uuid) {
+                    if !r.unchanged {
+                        changed.push(uuid);
+                    }
+                    meta.apply_fetch_result(r);
+                }
+            }
+            metas.flush().with_context(|| "try to flush MetadataMap")?;
+        }
+
+        // Snapshot history
+        for uuid in changed {
+            History::new(&uuid)
+                .snapshot("update")
+                .with_context(|| "try to snapshot profile history")?;
         }
-        metas.flush().with_context(|| "try to flush MetadataMap")?;
 
         // Success
-        success!("All profiles updated")
+        let r = success!("All profiles updated");
+        refresh_provider_cache().await;
+        warn_if_active_changed()?;
+        r
+    }
+}
+
+/// Show differences between the current Mihomo config and the rendered one
+fn show_render_diff(conf: &ProfileConfig) -> Result<()> {
+    let cfg = Config::get_instance();
+
+    // Render config
+    let contents = conf
+        .render()
+        .with_context(|| format!("try to render profile by UUID `{}`", conf.uuid))?;
+    let current = fs::read_to_string(&cfg.mihomo_path).unwrap_or_default();
+
+    // Warn problems
+    let problems =
+        validate_rendered(&contents).with_context(|| "try to validate rendered config")?;
+    for problem in &problems {
+        println!(
+            "{}",
+            console::style(format!("Problem {}", problem)).yellow()
+        );
+    }
+
+    // Build diff
+    let contents = diff::unified(
+        &current,
+        &contents,
+        &cfg.mihomo_path,
+        format!("{} (rendered)", conf.name),
+    );
+    if contents.trim().is_empty() {
+        return normal!("No differences");
+    }
+
+    // Show contents
+    file::show_contents("diff", &contents, false).with_context(|| "fail to show diff")?;
+
+    // Success
+    Ok(())
+}
+
+/// Record a profile activation into state
+pub fn record_activation(meta: &Meta, conf: &ProfileConfig, output: &str) -> Result<()> {
+    let mut state = State::load().with_context(|| "try to load state")?;
+    state
+        .record_activation(meta, conf, output)
+        .with_context(|| "try to record activation")?;
+    state.flush().with_context(|| "try to flush state")
+}
+
+/// Download cached providers of localized activations again, warn on failure
+async fn refresh_provider_cache() {
+    match refresh_providers().await {
+        Ok(0) => (),
+        Ok(count) => println!(
+            "{}",
+            console::style(format!("{} cached providers refreshed", count)).green()
+        ),
+        Err(err) => println!(
+            "{}",
+            console::style(format!("Fail to refresh provider cache: {:#}", err)).yellow()
+        ),
     }
 }
 
+/// Warn if the active profile's data changed since activation
+fn warn_if_active_changed() -> Result<()> {
+    let state = State::load().with_context(|| "try to load state")?;
+    let meta = match &state.active {
+        Some(uuid) => Metas::get_instance().lock().unwrap().get(uuid).cloned(),
+        None => None,
+    };
+    let Some(meta) = meta else {
+        return Ok(());
+    };
+
+    let conf = meta
+        .try_get_conf()
+        .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+    if state
+        .is_data_changed(&conf)
+        .with_context(|| format!("try to check profile data by UUID `{}`", meta.uuid))?
+    {
+        println!(
+            "{}",
+            console::style(format!(
+                "Profile `{}` is activated and its data changed since activation, activate it again to apply",
+                meta.name
+            ))
+            .yellow()
+        );
+    }
+
+    Ok(())
+}
+
+/// Get profile config contents from the editor, or from fields and file given on command line
+fn get_conf_contents<S: AsRef<str>>(args: &ConfigArgs, default_contents: S) -> Result<String> {
+    // Edit in editor if nothing is given on command line
+    if !args.is_set() {
+        let cfg = Config::get_instance();
+        return file::edit_temp_file(".yaml", Some(&cfg.editor), Some(default_contents))
+            .with_context(|| "try to edit temporary contents");
+    }
+
+    // Read base contents
+    let mut contents = match &args.from_file {
+        Some(path) => file::read_input(path)
+            .with_context(|| format!("try to read file `{}`", path.display()))?,
+        None => default_contents.as_ref().to_owned(),
+    };
+
+    // Set given fields
+    for (key, value) in args.get_fields() {
+        contents = set_conf_field(&contents, key, value)
+            .with_context(|| format!("try to set profile field `{}`", key))?;
+    }
+
+    Ok(contents)
+}
+
+/// Set a top-level field in profile config contents, keeping comments
+fn set_conf_field<S1, S2>(contents: S1, key: &str, value: S2) -> Result<String>
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    // Replace the field line, or uncomment the template one
+    let quoted = serde_yaml::to_string(value.as_ref())?;
+    let line = format!("{}: {}", key, quoted.trim());
+    for pattern in [r"(?m)^{}:.*$", r"(?m)^# {}:.*$"] {
+        let re = Regex::new(&pattern.replace("{}", &regex::escape(key)))?;
+        if re.is_match(contents.as_ref()) {
+            return Ok(re.replace(contents.as_ref(), NoExpand(&line)).into_owned());
+        }
+    }
+
+    // Fallback to re-serializing
+    let mut value_map = serde_yaml::from_str::<serde_yaml::Value>(contents.as_ref())?;
+    value_map[key] = value.as_ref().into();
+    Ok(serde_yaml::to_string(&value_map)?)
+}
+
+/// Fetch a remote profile, then save its metadata and history
+///
+/// Returns the updated metadata and whether the profile data changed
+pub async fn update_one<S: AsRef<str>>(uuid_or_name: S) -> Result<(Meta, bool)> {
+    let uuid_or_name = uuid_or_name.as_ref();
+
+    // Get profile metadata
+    let mut meta = Metas::get_instance()
+        .lock()
+        .unwrap()
+        .try_get_meta(uuid_or_name)
+        .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?
+        .clone();
+
+    // Get profile config
+    let conf = meta
+        .try_get_conf()
+        .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+
+    // Fetch, keeping the data before the first update
+    History::new(&meta.uuid)
+        .snapshot_initial()
+        .with_context(|| "try to snapshot profile history")?;
+    let r = conf
+        .fetch(&meta)
+        .await
+        .with_context(|| format!("try to fetch profile data by UUID `{}`", meta.uuid))?;
+    let changed = !r.unchanged;
+
+    // Update metadata
+    meta.apply_fetch_result(r);
+    {
+        let mut metas = Metas::get_instance().lock().unwrap();
+        metas.insert(meta.uuid.clone(), meta.clone());
+        metas.flush().with_context(|| "try to flush MetadataMap")?;
+    }
+
+    // Snapshot history
+    if changed {
+        History::new(&meta.uuid)
+            .snapshot("update")
+            .with_context(|| "try to snapshot profile history")?;
+    }
+
+    Ok((meta, changed))
+}
+
 pub fn view_conf(uuid_or_name: String) -> Result<()> {
     let metas = Metas::get_instance().lock().unwrap();
 
@@ -386,9 +1385,19 @@ pub fn view_conf(uuid_or_name: String) -> Result<()> {
         .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?
         .clone();
 
-    // Show file
+    // Redact credentials
     let path = path::get_profile_conf_dir().join(format!("{}.yaml", meta.uuid));
-    file::show_file(&path).with_context(|| format!("try to show file `{}`", path.display()))?;
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("try to read file `{}`", path.display()))?;
+    let contents = ProfileConfig::redact_contents(&contents);
+
+    // Show contents
+    file::show_contents(
+        path.file_name().map_or("", |v| v.to_str().unwrap_or("")),
+        &contents,
+        true,
+    )
+    .with_context(|| format!("try to show file `{}`", path.display()))?;
 
     // Success
     Ok(())
@@ -404,7 +1413,13 @@ pub fn view_data(uuid_or_name: String) -> Result<()> {
         .clone();
 
     // Show file
-    let path = path::get_profile_data_dir().join(format!("{}.yaml", meta.uuid));
+    let conf = meta
+        .try_get_conf()
+        .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+    if conf.r#type == ProfileConfigType::Composite {
+        bail!("data of composite profile is composed from its member profiles, use `profile render` to view it");
+    }
+    let path = conf.get_data_path();
     if !path.is_file() {
         bail!("data not found, maybe you have not edited the local profile's data or updated the remote profile yet");
     }
@@ -424,7 +1439,13 @@ pub fn view_rules(uuid_or_name: String) -> Result<()> {
         .clone();
 
     // Extract rules
-    let path = path::get_profile_data_dir().join(format!("{}.yaml", meta.uuid));
+    let conf = meta
+        .try_get_conf()
+        .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+    if conf.r#type == ProfileConfigType::Composite {
+        bail!("data of composite profile is composed from its member profiles, use `profile render` to view it");
+    }
+    let path = conf.get_data_path();
     if !path.is_file() {
         bail!("data not found, maybe you have not edited the local profile's data or updated the remote profile yet");
     }
@@ -4,7 +4,10 @@ use anyhow::{Context, Result};
 
 use crate::{
     data::config::Config,
-    utils::{self, file},
+    utils::{
+        self, file,
+        output::{self, OutputFormat},
+    },
 };
 
 pub fn profile() -> Result<()> {
@@ -20,7 +23,7 @@ pub fn profile() -> Result<()> {
     Ok(())
 }
 
-pub fn rules() -> Result<()> {
+pub fn rules(format: OutputFormat) -> Result<()> {
     let cfg = Config::get_instance();
 
     // Extract rules
@@ -29,6 +32,11 @@ pub fn rules() -> Result<()> {
         .with_context(|| format!("try to canonicalize path `{}`", cfg.mihomo_path))?;
     let contents = fs::read_to_string(&path)
         .with_context(|| format!("try to read file `{}`", path.display()))?;
+    if format != OutputFormat::Table {
+        let rules = utils::parse_rules(&contents)
+            .with_context(|| format!("try to parse rules `{}`", path.display()))?;
+        return output::print_serialized(&rules, format);
+    }
     let contents = utils::extract_rules(&contents)
         .with_context(|| format!("try to extract rules `{}`", path.display()))?;
 
@@ -1,11 +1,15 @@
 use std::{cmp::Ordering, collections::HashMap, sync::LazyLock};
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     data::config::Config,
-    utils::{file, result::normal},
+    utils::{
+        file,
+        output::{self, OutputFormat},
+        result::normal,
+    },
 };
 
 pub static GROUP_TYPE_ORDERING: LazyLock<HashMap<String, i32>> = LazyLock::new(|| {
@@ -21,7 +25,7 @@ pub static GROUP_TYPE_ORDERING: LazyLock<HashMap<String, i32>> = LazyLock::new(|
 pub async fn configs() -> Result<()> {
     // Call API
     let conf = Config::get_instance()
-        .get_api()
+        .get_api()?
         .get_configs()
         .await
         .with_context(|| "try to call api")?;
@@ -34,10 +38,10 @@ pub async fn configs() -> Result<()> {
     Ok(())
 }
 
-pub async fn connections() -> Result<()> {
+pub async fn connections(format: OutputFormat) -> Result<()> {
     // Call API
     let value = Config::get_instance()
-        .get_api()
+        .get_api()?
         .get_connections()
         .await
         .with_context(|| "try to call api")?
@@ -46,19 +50,19 @@ pub async fn connections() -> Result<()> {
         .get("connections")
         .unwrap()
         .to_owned();
-    if value.is_null() {
+    if value.is_null() && format == OutputFormat::Table {
         return normal!("No connection");
     }
 
     // Parse data
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Serialize)]
     struct Conn {
         metadata: ConnMeta,
         start: String,
         chains: Vec<String>,
         rule: String,
     }
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Serialize)]
     struct ConnMeta {
         network: String,
         r#type: String,
@@ -72,9 +76,13 @@ pub async fn connections() -> Result<()> {
         destination_port: String,
         host: String,
     }
-    let mut value =
-        serde_json::from_value::<Vec<Conn>>(value).with_context(|| "try to parse data")?;
+    let mut value = serde_json::from_value::<Option<Vec<Conn>>>(value)
+        .with_context(|| "try to parse data")?
+        .unwrap_or_default();
     value.sort_by_key(|v| v.start.to_owned());
+    if format != OutputFormat::Table {
+        return output::print_serialized(&value, format);
+    }
 
     // Print connections
     for conn in value {
@@ -82,7 +90,7 @@ pub async fn connections() -> Result<()> {
             "[{}:{}] {}({}:{}) {} {}",
             console::style(conn.metadata.network).bright().green(),
             console::style(conn.metadata.r#type).bright().cyan(),
-            if conn.metadata.host.len() == 0 {
+            if conn.metadata.host.is_empty() {
                 console::style("No host".to_owned()).bright().black()
             } else {
                 console::style(conn.metadata.host).bold().bright().blue()
@@ -108,10 +116,10 @@ pub async fn connections() -> Result<()> {
     Ok(())
 }
 
-pub async fn groups() -> Result<()> {
+pub async fn groups(format: OutputFormat) -> Result<()> {
     // Call API
     let value = Config::get_instance()
-        .get_api()
+        .get_api()?
         .get_groups()
         .await
         .with_context(|| "try to call api")?
@@ -122,7 +130,7 @@ pub async fn groups() -> Result<()> {
         .to_owned();
 
     // Parse data
-    #[derive(Deserialize)]
+    #[derive(Deserialize, Serialize)]
     struct Group {
         name: String,
         now: String,
@@ -145,6 +153,10 @@ pub async fn groups() -> Result<()> {
         }
     });
 
+    if format != OutputFormat::Table {
+        return output::print_serialized(&groups, format);
+    }
+
     // Print groups
     let w = groups.len().to_string().len();
     for (idx, group) in groups.iter().enumerate() {
@@ -161,15 +173,22 @@ pub async fn groups() -> Result<()> {
     Ok(())
 }
 
-pub async fn version() -> Result<()> {
+pub async fn version(format: OutputFormat) -> Result<()> {
     // Call API
     let version = Config::get_instance()
-        .get_api()
+        .get_api()?
         .get_version()
         .await
         .with_context(|| "try to call api")?;
 
     // Print version
+    if format != OutputFormat::Table {
+        #[derive(Serialize)]
+        struct Version {
+            version: String,
+        }
+        return output::print_serialized(&Version { version }, format);
+    }
     println!("{version}");
 
     // Success
@@ -1,16 +1,28 @@
 use std::{
+    collections::HashMap,
     fmt::{Display, Formatter, Result as FmtResult},
-    fs::{self, File},
-    io::Write,
+    fs,
     path::PathBuf,
     sync::OnceLock,
 };
 
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
 use serde::Deserialize;
 use serde_yaml::Value;
 use url::Url;
 
-use crate::utils::{api::Api, path};
+use crate::utils::{api::Api, env, file, path};
+
+/// Fields kept as strings when their `${VAR}` references are expanded
+const STRING_FIELDS: [&str; 6] = [
+    "editor",
+    "mihomo_path",
+    "mihomo_api",
+    "mihomo_token",
+    "mihomo_token_file",
+    "mihomo_token_env",
+];
 
 #[derive(Deserialize)]
 pub struct Config {
@@ -18,6 +30,8 @@ pub struct Config {
     pub mihomo_path: String,
     pub mihomo_api: Url,
     pub mihomo_token: Option<String>,
+    pub mihomo_token_file: Option<String>,
+    pub mihomo_token_env: Option<String>,
     pub mode: ConfigMode,
     pub mixed_port: u16,
     pub socks_port: Option<u16>,
@@ -31,8 +45,31 @@ pub struct Config {
 
     pub extend_configs: Option<Value>,
     pub extend_scripts: Option<String>,
+
+    pub history_limit: Option<usize>,
+
+    pub fetch_timeout: Option<u64>,
+    pub fetch_retries: Option<u32>,
+    pub fetch_retry_backoff: Option<u64>,
+
+    pub activation_timeout: Option<u64>,
+
+    #[serde(default)]
+    pub activation_method: ConfigActivationMethod,
+
+    #[serde(default)]
+    pub localize_providers: bool,
+
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
 }
 impl Config {
+    pub const DEFAULT_HISTORY_LIMIT: usize = 10;
+    pub const DEFAULT_FETCH_TIMEOUT: u64 = 30;
+    pub const DEFAULT_FETCH_RETRIES: u32 = 2;
+    pub const DEFAULT_FETCH_RETRY_BACKOFF: u64 = 1;
+    pub const DEFAULT_ACTIVATION_TIMEOUT: u64 = 15;
+
     pub const DEFAULT_CONFIG: &'static [u8] = include_bytes!("../includes/default_config.yaml");
 
     pub fn get_instance() -> &'static Self {
@@ -40,24 +77,131 @@ impl Config {
         I.get_or_init(|| {
             let path = path::get_data_dir().join("config.yaml");
             if !path.is_file() {
-                let mut file = File::create(&path).expect("fail to create config file");
-                file.write_all(&Self::DEFAULT_CONFIG)
+                file::write_private(&path, Self::DEFAULT_CONFIG)
                     .expect("fail to write config file");
-                file.flush().expect("fail to flush config file");
             }
 
             let contents = fs::read_to_string(&path).expect("fail to read config file");
-            serde_yaml::from_str(&contents).expect("fail to parse config file")
+            Self::from_str(&contents).expect("fail to parse config file")
         })
     }
 
+    /// Parse config contents, with `${VAR}` references expanded
+    pub fn from_str<S: AsRef<str>>(contents: S) -> Result<Self> {
+        let mut value = serde_yaml::from_str::<Value>(contents.as_ref())?;
+
+        // Collect variables, scalar values are converted to strings
+        let mut variables = HashMap::new();
+        if let Some(map) = value.get("variables").and_then(Value::as_mapping) {
+            for (k, v) in map {
+                let (Some(k), Some(v)) = (k.as_str(), scalar_to_string(v)) else {
+                    continue;
+                };
+                variables.insert(k.to_owned(), v);
+            }
+        }
+
+        // Expand references
+        if let Some(map) = value.as_mapping_mut() {
+            for (k, v) in map.iter_mut() {
+                let k = k.as_str().unwrap_or_default();
+                if k == "variables" {
+                    *v = serde_yaml::to_value(&variables)?;
+                    continue;
+                }
+
+                // Scripts are kept as they are, `${...}` is a JavaScript template literal there
+                if k == "extend_scripts" {
+                    continue;
+                }
+                match v {
+                    Value::String(s) if STRING_FIELDS.contains(&k) => {
+                        *s = env::expand(&*s, &variables)
+                            .with_context(|| format!("try to expand `{}`", k))?;
+                    }
+                    _ => env::expand_value(v, &variables)
+                        .with_context(|| format!("try to expand `{}`", k))?,
+                }
+            }
+        }
+
+        // Verify token sources
+        let value = serde_yaml::from_value::<Self>(value)?;
+        let sources = [
+            value.mihomo_token.is_some(),
+            value.mihomo_token_file.is_some(),
+            value.mihomo_token_env.is_some(),
+        ];
+        if sources.into_iter().filter(|v| *v).count() > 1 {
+            bail!(
+                "only one of `mihomo_token`, `mihomo_token_file` and `mihomo_token_env` can be set"
+            );
+        }
+
+        Ok(value)
+    }
+
+    /// Expand `${VAR}` references with environment variables and `variables`
+    pub fn expand<S: AsRef<str>>(&self, s: S) -> Result<String> {
+        env::expand(s, &self.variables)
+    }
+
     pub fn get_path(&self) -> PathBuf {
         path::get_data_dir().join("config.yaml")
     }
 
-    pub fn get_api(&self) -> Api {
-        Api::new(&self.mihomo_api, self.mihomo_token.clone())
+    pub fn get_history_limit(&self) -> usize {
+        self.history_limit.unwrap_or(Self::DEFAULT_HISTORY_LIMIT)
     }
+
+    pub fn get_fetch_timeout(&self) -> u64 {
+        self.fetch_timeout.unwrap_or(Self::DEFAULT_FETCH_TIMEOUT)
+    }
+
+    pub fn get_fetch_retries(&self) -> u32 {
+        self.fetch_retries.unwrap_or(Self::DEFAULT_FETCH_RETRIES)
+    }
+
+    pub fn get_fetch_retry_backoff(&self) -> u64 {
+        self.fetch_retry_backoff
+            .unwrap_or(Self::DEFAULT_FETCH_RETRY_BACKOFF)
+    }
+
+    pub fn get_activation_timeout(&self) -> u64 {
+        self.activation_timeout
+            .unwrap_or(Self::DEFAULT_ACTIVATION_TIMEOUT)
+    }
+
+    /// Get Mihomo API token, which may be read from a file or an environment variable
+    pub fn get_mihomo_token(&self) -> Result<Option<String>> {
+        if let Some(path) = &self.mihomo_token_file {
+            return file::read_secret(path)
+                .map(Some)
+                .with_context(|| "try to read `mihomo_token_file`");
+        }
+        if let Some(name) = &self.mihomo_token_env {
+            return std::env::var(name)
+                .map(Some)
+                .with_context(|| format!("environment variable `{}` is not set", name));
+        }
+
+        Ok(self.mihomo_token.clone())
+    }
+
+    pub fn get_api(&self) -> Result<Api> {
+        Ok(Api::new(&self.mihomo_api, self.get_mihomo_token()?))
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigActivationMethod {
+    /// Restart Mihomo, which drops all connections
+    #[default]
+    Restart,
+
+    /// Hot-reload the config, fallback to restart on failure
+    Reload,
 }
 
 #[derive(Clone, Deserialize)]
@@ -76,3 +220,36 @@ impl Display for ConfigMode {
         }
     }
 }
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(v) => Some(v.clone()),
+        Value::Number(v) => Some(v.to_string()),
+        Value::Bool(v) => Some(v.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_extend_scripts() {
+        let script = "function main(config) {\n  const name = `${config.mode}-${x}`;\n}\n";
+        let contents = format!(
+            "{}\nextend_scripts: |\n{}",
+            String::from_utf8_lossy(Config::DEFAULT_CONFIG).replace(
+                "mihomo_path: /etc/mihomo",
+                "mihomo_path: ${MIHOMOSH_TEST_DIR:-/etc/mihomo}"
+            ),
+            script
+                .lines()
+                .map(|v| format!("  {}\n", v))
+                .collect::<String>()
+        );
+        let value = Config::from_str(contents).unwrap();
+        assert_eq!(value.extend_scripts.as_deref(), Some(script));
+        assert_eq!(value.mihomo_path, "/etc/mihomo/config.yaml");
+    }
+}
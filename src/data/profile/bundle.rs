@@ -0,0 +1,173 @@
+use std::{collections::HashMap, fs};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::utils::path;
+
+use super::{ConfigType, Meta, Metas};
+
+/// Profile config fields stripped along with secrets
+const SECRET_FIELDS: [&str; 7] = [
+    "url",
+    "url_file",
+    "url_env",
+    "user_agent",
+    "headers",
+    "basic_auth",
+    "bearer_token",
+];
+
+/// Portable bundle of profiles
+#[derive(Deserialize, Serialize)]
+pub struct Bundle {
+    pub version: u32,
+    pub profiles: Vec<BundleProfile>,
+}
+impl Bundle {
+    pub const VERSION: u32 = 1;
+
+    /// Pack profiles, optionally stripping URLs and credentials
+    pub fn pack(metas: &[Meta], strip_secrets: bool) -> Result<Self> {
+        let names = Metas::get_instance()
+            .lock()
+            .unwrap()
+            .values()
+            .map(|v| (v.uuid.clone(), v.name.clone()))
+            .collect::<HashMap<_, _>>();
+
+        let mut profiles = Vec::new();
+        for meta in metas {
+            profiles.push(
+                BundleProfile::pack(meta, strip_secrets, &names)
+                    .with_context(|| format!("try to pack profile `{}`", meta.name))?,
+            );
+        }
+
+        Ok(Self {
+            version: Self::VERSION,
+            profiles,
+        })
+    }
+
+    pub fn from_str<S: AsRef<str>>(contents: S) -> Result<Self> {
+        let value = serde_yaml::from_str::<Self>(contents.as_ref())?;
+        if value.version != Self::VERSION {
+            bail!("unsupported bundle version `{}`", value.version);
+        }
+
+        Ok(value)
+    }
+}
+
+/// Profile packed in a bundle
+#[derive(Deserialize, Serialize)]
+pub struct BundleProfile {
+    pub meta: Meta,
+    pub config: String,
+    pub data: Option<String>,
+}
+impl BundleProfile {
+    fn pack(meta: &Meta, strip_secrets: bool, names: &HashMap<String, String>) -> Result<Self> {
+        let conf = meta.try_get_conf()?;
+
+        // Read config
+        let path = path::get_profile_conf_dir().join(format!("{}.yaml", meta.uuid));
+        let mut config = fs::read_to_string(&path)
+            .with_context(|| format!("try to read file `{}`", path.display()))?;
+
+        // Read data
+        let path = conf.get_data_path();
+        let data = if path.is_file() {
+            Some(
+                fs::read_to_string(&path)
+                    .with_context(|| format!("try to read file `{}`", path.display()))?,
+            )
+        } else {
+            None
+        };
+
+        // File path is machine specific, pack it as a local profile
+        // Raw configs are edited, so that `${VAR}` references are kept
+        let mut meta = meta.clone();
+        if conf.r#type == ConfigType::File {
+            let mut value = serde_yaml::from_str::<Value>(&config)?;
+            value["type"] = ConfigType::Local.to_string().into();
+            if let Some(map) = value.as_mapping_mut() {
+                map.remove("path");
+            }
+            config = serde_yaml::to_string(&value)?;
+        }
+
+        // UUIDs are regenerated on import, refer to member and template profiles by name
+        let mut value = serde_yaml::from_str::<Value>(&config)?;
+        if refer_by_name(&mut value, names) {
+            config = serde_yaml::to_string(&value)?;
+        }
+
+        // Strip secrets, remote profiles without URL become local ones
+        if strip_secrets {
+            let mut value = serde_yaml::from_str::<Value>(&config)?;
+            if conf.r#type == ConfigType::Remote {
+                value["type"] = ConfigType::Local.to_string().into();
+            }
+            if let Some(map) = value.as_mapping_mut() {
+                for key in SECRET_FIELDS {
+                    map.remove(key);
+                }
+            }
+            config = serde_yaml::to_string(&value)?;
+
+            meta.remote = false;
+            meta.web_page_url = None;
+            meta.etag = None;
+            meta.last_modified = None;
+        }
+
+        Ok(Self { meta, config, data })
+    }
+}
+
+/// Replace UUIDs of member and template profiles in a raw config with profile names, returns
+/// whether any is replaced
+fn refer_by_name(value: &mut Value, names: &HashMap<String, String>) -> bool {
+    let mut replaced = false;
+    let mut rename = |v: &mut Value| {
+        if let Some(name) = v.as_str().and_then(|v| names.get(v)) {
+            *v = name.clone().into();
+            replaced = true;
+        }
+    };
+    if let Some(members) = value.get_mut("profiles").and_then(Value::as_sequence_mut) {
+        members.iter_mut().for_each(&mut rename);
+    }
+    if let Some(template) = value.get_mut("template") {
+        rename(template);
+    }
+
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refer_profiles_by_name() {
+        let names = HashMap::from([
+            ("0a1b2c3d".to_owned(), "Home".to_owned()),
+            ("4e5f6a7b".to_owned(), "Work".to_owned()),
+        ]);
+        let mut value = serde_yaml::from_str::<Value>(
+            "type: composite\nprofiles: [0a1b2c3d, Work, ffffffff]\ntemplate: 4e5f6a7b\n",
+        )
+        .unwrap();
+        assert!(refer_by_name(&mut value, &names));
+        assert_eq!(
+            serde_yaml::to_string(&value).unwrap(),
+            "type: composite\nprofiles:\n- Home\n- Work\n- ffffffff\ntemplate: Work\n"
+        );
+        assert!(!refer_by_name(&mut value, &names));
+    }
+}
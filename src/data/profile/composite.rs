@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use super::{
+    validate::BUILTIN_PROXIES, Config, ConfigPool, ConfigType, Metas, URL_TEST_INTERVAL,
+    URL_TEST_URL,
+};
+
+/// Name of the generated selector group over all member profiles
+const PROXY_GROUP: &str = "PROXY";
+
+/// Name of the generated url-test group over all nodes
+const AUTO_GROUP: &str = "Auto";
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Data {
+    proxies: Vec<Value>,
+    proxy_groups: Vec<Group>,
+
+    #[serde(skip_serializing_if = "Mapping::is_empty")]
+    proxy_providers: Mapping,
+
+    rules: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Group {
+    name: String,
+    r#type: &'static str,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    proxies: Vec<String>,
+
+    #[serde(rename = "use", skip_serializing_if = "Vec::is_empty")]
+    providers: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'static str>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interval: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Provider {
+    r#type: &'static str,
+    payload: Vec<Value>,
+    r#override: ProviderOverride,
+    health_check: ProviderHealthCheck,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ProviderOverride {
+    additional_prefix: String,
+}
+
+#[derive(Serialize)]
+struct ProviderHealthCheck {
+    enable: bool,
+    url: &'static str,
+    interval: u64,
+}
+
+/// Compose the data of a composite profile from its member profiles
+pub fn compose(conf: &Config) -> Result<String> {
+    // Load member profiles
+    let members = {
+        let metas = Metas::get_instance().lock().unwrap();
+        let mut members = Vec::new();
+        for uuid_or_name in &conf.profiles {
+            let meta = metas
+                .try_get_meta(uuid_or_name)
+                .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?;
+            let member = meta
+                .try_get_conf()
+                .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?;
+            if member.r#type == ConfigType::Composite {
+                bail!("composite profile `{}` cannot be a member", member.name);
+            }
+            members.push(member);
+        }
+        members
+    };
+    let mut datas = Vec::new();
+    for member in members {
+        let data = member.read_data()?;
+        datas.push((member.name, data));
+    }
+
+    build(&datas, conf.pool.unwrap_or_default())
+}
+
+/// Build composite data from names and data of member profiles
+fn build(members: &[(String, String)], pool: ConfigPool) -> Result<String> {
+    // Reserve group names, nodes with the same names are numbered below
+    let mut names = BUILTIN_PROXIES
+        .into_iter()
+        .chain([PROXY_GROUP, AUTO_GROUP])
+        .map(str::to_owned)
+        .collect::<HashSet<_>>();
+    for (name, _) in members {
+        if !names.insert(name.clone()) {
+            bail!(
+                "member profile name `{}` collides with another member or a generated group",
+                name
+            );
+        }
+    }
+
+    // Pool nodes of member profiles
+    let mut data = Data {
+        proxies: Vec::new(),
+        proxy_groups: Vec::new(),
+        proxy_providers: Mapping::new(),
+        rules: vec![format!("MATCH,{}", PROXY_GROUP)],
+    };
+    let mut groups = Vec::new();
+    for (member, contents) in members {
+        let value = serde_yaml::from_str::<Value>(contents)
+            .with_context(|| format!("try to parse data of profile `{}`", member))?;
+        let nodes = value
+            .get("proxies")
+            .and_then(Value::as_sequence)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut group = Group {
+            name: member.clone(),
+            r#type: "select",
+            proxies: Vec::new(),
+            providers: Vec::new(),
+            url: None,
+            interval: None,
+        };
+        match pool {
+            // Copy nodes with profile name prefixed and duplicated names numbered
+            ConfigPool::Merge => {
+                for mut node in nodes {
+                    let Some(name) = node.get("name").and_then(Value::as_str) else {
+                        continue;
+                    };
+                    let prefixed = format!("{} | {}", member, name);
+                    let mut name = prefixed.clone();
+                    let mut idx = 2;
+                    while !names.insert(name.clone()) {
+                        name = format!("{} ({})", prefixed, idx);
+                        idx += 1;
+                    }
+                    node["name"] = name.clone().into();
+                    group.proxies.push(name);
+                    data.proxies.push(node);
+                }
+                if group.proxies.is_empty() {
+                    group.proxies.push("DIRECT".into());
+                }
+            }
+
+            // Expose nodes as an inline provider with profile name prefixed
+            ConfigPool::Provider => {
+                let provider = Provider {
+                    r#type: "inline",
+                    payload: nodes,
+                    r#override: ProviderOverride {
+                        additional_prefix: format!("{} | ", member),
+                    },
+                    health_check: ProviderHealthCheck {
+                        enable: true,
+                        url: URL_TEST_URL,
+                        interval: URL_TEST_INTERVAL,
+                    },
+                };
+                data.proxy_providers
+                    .insert(member.clone().into(), serde_yaml::to_value(provider)?);
+                group.providers.push(member.clone());
+            }
+        }
+        groups.push(group);
+    }
+
+    // Build groups over the union
+    let mut selector = vec![AUTO_GROUP.to_owned()];
+    selector.extend(members.iter().map(|(v, _)| v.clone()));
+    selector.push("DIRECT".into());
+    data.proxy_groups.push(Group {
+        name: PROXY_GROUP.into(),
+        r#type: "select",
+        proxies: selector,
+        providers: Vec::new(),
+        url: None,
+        interval: None,
+    });
+    let (mut proxies, providers) = match pool {
+        ConfigPool::Merge => (
+            data.proxies
+                .iter()
+                .filter_map(|v| v.get("name").and_then(Value::as_str))
+                .map(str::to_owned)
+                .collect::<Vec<_>>(),
+            Vec::new(),
+        ),
+        ConfigPool::Provider => (Vec::new(), members.iter().map(|(v, _)| v.clone()).collect()),
+    };
+    if proxies.is_empty() && providers.is_empty() {
+        proxies.push("DIRECT".into());
+    }
+    data.proxy_groups.push(Group {
+        name: AUTO_GROUP.into(),
+        r#type: "url-test",
+        proxies,
+        providers,
+        url: Some(URL_TEST_URL),
+        interval: Some(URL_TEST_INTERVAL),
+    });
+    data.proxy_groups.extend(groups);
+
+    Ok(serde_yaml::to_string(&data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members() -> Vec<(String, String)> {
+        vec![
+            (
+                "Home".into(),
+                "proxies:\n- {name: a, type: socks5}\n- {name: b, type: socks5}\n".into(),
+            ),
+            (
+                "Work".into(),
+                "proxies:\n- {name: a, type: socks5}\n- {name: Home | a, type: socks5}\n".into(),
+            ),
+            ("Empty".into(), "rules: []\n".into()),
+        ]
+    }
+
+    fn names(value: &Value, key: &str) -> Vec<String> {
+        value[key]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn merge_pool() {
+        let value =
+            serde_yaml::from_str::<Value>(&build(&members(), ConfigPool::Merge).unwrap()).unwrap();
+        let proxies = value["proxies"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|v| v["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            proxies,
+            ["Home | a", "Home | b", "Work | a", "Work | Home | a"]
+        );
+
+        let groups = &value["proxy-groups"];
+        assert_eq!(
+            names(&groups[0], "proxies"),
+            ["Auto", "Home", "Work", "Empty", "DIRECT"]
+        );
+        assert_eq!(names(&groups[1], "proxies"), proxies);
+        assert_eq!(names(&groups[2], "proxies"), ["Home | a", "Home | b"]);
+        assert_eq!(names(&groups[4], "proxies"), ["DIRECT"]);
+        assert!(value.get("proxy-providers").is_none());
+        assert_eq!(names(&value, "rules"), ["MATCH,PROXY"]);
+    }
+
+    #[test]
+    fn merge_pool_dedupe() {
+        let members = vec![
+            ("A | b".into(), "proxies:\n- {name: c}\n".into()),
+            (
+                "A".into(),
+                "proxies:\n- {name: b | c}\n- {name: b | c}\n".into(),
+            ),
+        ];
+        let value =
+            serde_yaml::from_str::<Value>(&build(&members, ConfigPool::Merge).unwrap()).unwrap();
+        assert_eq!(
+            names(&value["proxy-groups"][1], "proxies"),
+            ["A | b | c", "A | b | c (2)", "A | b | c (3)"]
+        );
+
+        let members = vec![("Auto".into(), String::new())];
+        assert!(build(&members, ConfigPool::Merge).is_err());
+    }
+
+    #[test]
+    fn provider_pool() {
+        let value =
+            serde_yaml::from_str::<Value>(&build(&members(), ConfigPool::Provider).unwrap())
+                .unwrap();
+        assert!(value["proxies"].as_sequence().unwrap().is_empty());
+
+        let provider = &value["proxy-providers"]["Work"];
+        assert_eq!(provider["type"], Value::from("inline"));
+        assert_eq!(provider["payload"].as_sequence().unwrap().len(), 2);
+        assert_eq!(
+            provider["override"]["additional-prefix"],
+            Value::from("Work | ")
+        );
+        assert_eq!(provider["health-check"]["url"], Value::from(URL_TEST_URL));
+        assert!(provider["payload"][0].get("name").is_some());
+
+        let groups = &value["proxy-groups"];
+        assert!(groups[1].get("proxies").is_none());
+        assert_eq!(names(&groups[1], "use"), ["Home", "Work", "Empty"]);
+        assert_eq!(names(&groups[3], "use"), ["Work"]);
+    }
+}
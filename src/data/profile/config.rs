@@ -1,46 +1,164 @@
-use std::{fs, time::Duration};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    path::PathBuf,
+    time::Duration,
+};
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, bail, Context as _, Result};
 use boa_engine::{js_string, property::Attribute, vm::RuntimeLimits, Context, Source};
 use boa_runtime::RegisterOptions;
-use reqwest::Client;
-use serde::Deserialize;
-use serde_yaml::Value;
+use reqwest::{
+    header::{
+        AsHeaderName, CONTENT_DISPOSITION, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+        USER_AGENT,
+    },
+    Client, Proxy, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
 use url::Url;
 
-use crate::{data::config::Config as AppConfig, utils::path};
+use crate::{
+    data::config::{Config as AppConfig, ConfigActivationMethod},
+    utils::{self, env, file, path},
+};
 
-#[derive(Clone, Deserialize)]
+use super::{composite, localize_providers, share_link, template, validate_rendered, Meta};
+
+/// User agent filled in for new remote profiles
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "mihomo-utils/v",
+    env!("CARGO_PKG_VERSION"),
+    " (clash-verge)"
+);
+
+/// String fields whose `${VAR}` references are expanded on loading, besides `extend_configs`
+const EXPANDED_FIELDS: [&str; 2] = ["url", "user_agent"];
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Config {
     #[serde(skip)]
     pub uuid: String,
 
     pub name: String,
     pub r#type: ConfigType,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<Url>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_file: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_env: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub user_agent: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update_interval: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool: Option<ConfigPool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+
     #[serde(default)]
     pub use_proxy: ConfigProxy,
 
     #[serde(default)]
-    pub allow_invalid_certs: bool,
+    pub proxy_fallback: bool,
 
     #[serde(default)]
+    pub allow_invalid_certs: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_backoff: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub basic_auth: Option<ConfigBasicAuth>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bearer_token: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub extend_configs: Option<Value>,
 
-    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub extend_scripts: Option<String>,
 }
 impl Config {
+    /// Parse profile config contents, with `${VAR}` references expanded in the fields
+    /// which cannot reference variables at request time
+    pub fn from_str<S: AsRef<str>>(contents: S) -> Result<Self> {
+        let cfg = AppConfig::get_instance();
+
+        let mut value = serde_yaml::from_str::<Value>(contents.as_ref())?;
+        for key in EXPANDED_FIELDS {
+            if let Some(Value::String(v)) = value.get_mut(key) {
+                *v = env::expand(&*v, &cfg.variables)
+                    .with_context(|| format!("try to expand `{}`", key))?;
+            }
+        }
+        if let Some(v) = value.get_mut("extend_configs") {
+            env::expand_value(v, &cfg.variables)
+                .with_context(|| "try to expand `extend_configs`")?;
+        }
+
+        Ok(serde_yaml::from_value(value)?)
+    }
+
     pub fn verify(&self) -> Result<()> {
-        if self.name.trim().len() == 0 {
+        if self.name.trim().is_empty() {
             bail!("profile name cannot be empty");
         }
 
-        if self.r#type == ConfigType::Remote && (self.url.is_none() || self.user_agent.is_none()) {
+        let urls = [
+            self.url.is_some(),
+            self.url_file.is_some(),
+            self.url_env.is_some(),
+        ];
+        let urls = urls.into_iter().filter(|v| *v).count();
+        if self.r#type == ConfigType::Remote && (urls == 0 || self.user_agent.is_none()) {
             bail!("profile URL and user agent cannot be empty when type is `remote`");
         }
+        if urls > 1 {
+            bail!("only one of profile `url`, `url_file` and `url_env` can be set");
+        }
+
+        if self.r#type == ConfigType::File && self.path.is_none() {
+            bail!("profile path cannot be empty when type is `file`");
+        }
+
+        if self.r#type == ConfigType::Composite && self.profiles.is_empty() {
+            bail!("profile member profiles cannot be empty when type is `composite`");
+        }
+
+        if self.r#type == ConfigType::Composite && self.template.is_some() {
+            bail!("profile template cannot be set when type is `composite`");
+        }
+
+        if self.basic_auth.is_some() && self.bearer_token.is_some() {
+            bail!("profile basic auth and bearer token cannot be both set");
+        }
 
         if let Some(v) = &self.extend_configs {
             if !v.is_mapping() {
@@ -51,80 +169,267 @@ impl Config {
         Ok(())
     }
 
-    pub async fn fetch(&self) -> Result<(Option<usize>, Option<usize>, Option<i64>)> {
+    pub async fn fetch(&self, meta: &Meta) -> Result<FetchResult> {
         // Check type
         if self.r#type != ConfigType::Remote {
             bail!("cannot fetch on a local profile");
         }
 
-        // Fetch data
+        // Only send cache validators when the cached data is still there
+        let path = path::get_profile_data_dir().join(format!("{}.yaml", self.uuid));
+        let cached = if path.is_file() { Some(meta) } else { None };
+
+        // Fetch data, retry on transient errors
+        let retries = self.get_retries();
+        let mut attempt = 0;
+        let (result, body) = loop {
+            attempt += 1;
+            let err = match self.send_with_fallback(cached).await {
+                Ok(r) if r.status().is_server_error() => {
+                    anyhow!("server responded with status `{}`", r.status())
+                }
+                Ok(r) if !r.status().is_success() && r.status() != StatusCode::NOT_MODIFIED => {
+                    bail!("server responded with status `{}`", r.status());
+                }
+                Ok(r) => {
+                    let result = parse_headers(&r);
+                    if result.unchanged {
+                        break (result, None);
+                    }
+                    match r.text().await {
+                        Ok(body) => break (result, Some(body)),
+                        Err(err) => err.without_url().into(),
+                    }
+                }
+                Err(err) if is_transient(&err) => err,
+                Err(err) => return Err(err),
+            };
+            if attempt > retries {
+                return Err(err);
+            }
+
+            // Wait before next attempt
+            let delay = self.get_retry_backoff() * 2u32.pow((attempt - 1).min(6));
+            println!(
+                "{}",
+                console::style(format!(
+                    "Profile `{}` attempt {}/{} failed, retry in {}s: {:#}",
+                    self.name,
+                    attempt,
+                    retries + 1,
+                    delay.as_secs_f32(),
+                    err
+                ))
+                .yellow()
+            );
+            tokio::time::sleep(delay).await;
+        };
+        let body = match body {
+            Some(v) => v,
+            None => return Ok(result),
+        };
+
+        // Convert share links
+        let converted = share_link::convert(body)?;
+        if converted.skipped > 0 {
+            println!(
+                "{}",
+                console::style(format!(
+                    "Profile `{}` skipped {} invalid or unsupported share links",
+                    self.name, converted.skipped
+                ))
+                .yellow()
+            );
+        }
+        let contents = converted.contents;
+
+        // Verify data, keep the old file untouched on failure
+        verify_data(&contents).with_context(|| "try to verify fetched data")?;
+
+        // Save file
+        file::write_private(&path, contents)?;
+
+        // Success
+        Ok(result)
+    }
+
+    /// Get data file path, file profiles track an external path
+    pub fn get_data_path(&self) -> PathBuf {
+        match (&self.r#type, &self.path) {
+            (ConfigType::File, Some(v)) => path::expand_home(v),
+            _ => path::get_profile_data_dir().join(format!("{}.yaml", self.uuid)),
+        }
+    }
+
+    /// Get profile URL, which may be read from a file or an environment variable
+    pub fn get_url(&self) -> Result<Option<Url>> {
+        let url = if let Some(path) = &self.url_file {
+            file::read_secret(path).with_context(|| "try to read profile `url_file`")?
+        } else if let Some(name) = &self.url_env {
+            std::env::var(name)
+                .with_context(|| format!("environment variable `{}` is not set", name))?
+        } else {
+            return Ok(self.url.clone());
+        };
+
+        Ok(Some(
+            Url::parse(&url).with_context(|| "try to parse profile URL")?,
+        ))
+    }
+
+    /// Get the URL with secret parts redacted
+    pub fn get_redacted_url(&self) -> Option<String> {
+        let url = self.get_url().ok().flatten()?;
+
+        Some(utils::redact_url(&url))
+    }
+
+    /// Mask request credentials and secret parts of the URL in profile config contents
+    pub fn redact_contents<S: AsRef<str>>(contents: S) -> String {
+        utils::redact_yaml(contents, |keys, value| match keys {
+            ["headers", _] | ["basic_auth", "password"] | ["bearer_token"] => {
+                Some("******".to_owned())
+            }
+            ["headers" | "basic_auth"] if !value.is_empty() => Some("******".to_owned()),
+            ["url"] => Url::parse(value.trim_matches(['"', '\'']))
+                .map(|v| utils::redact_url(&v))
+                .ok(),
+            _ => None,
+        })
+    }
+
+    fn get_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.timeout
+                .unwrap_or(AppConfig::get_instance().get_fetch_timeout()),
+        )
+    }
+
+    fn get_retries(&self) -> u32 {
+        self.retries
+            .unwrap_or(AppConfig::get_instance().get_fetch_retries())
+    }
+
+    fn get_retry_backoff(&self) -> Duration {
+        Duration::from_secs(
+            self.retry_backoff
+                .unwrap_or(AppConfig::get_instance().get_fetch_retry_backoff()),
+        )
+    }
+
+    async fn send_with_fallback(&self, cached: Option<&Meta>) -> Result<Response> {
+        match self.send(&self.use_proxy, cached).await {
+            Ok(r) => Ok(r),
+            Err(err) if self.proxy_fallback && self.use_proxy == ConfigProxy::Mihomo => {
+                println!(
+                    "{}",
+                    console::style(format!(
+                        "Profile `{}` fail to fetch through Mihomo, fallback to direct fetch: {:#}",
+                        self.name, err
+                    ))
+                    .yellow()
+                );
+                self.send(&ConfigProxy::None, cached).await
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn send(&self, proxy: &ConfigProxy, cached: Option<&Meta>) -> Result<Response> {
+        // Create client
         let mut builder = Client::builder()
             .danger_accept_invalid_certs(self.allow_invalid_certs)
-            .timeout(Duration::from_secs(30));
-        match self.use_proxy {
+            .timeout(self.get_timeout());
+        match proxy {
             ConfigProxy::None => builder = builder.no_proxy(),
             ConfigProxy::System => (),
-            ConfigProxy::Mihomo => todo!(),
+            ConfigProxy::Mihomo => {
+                let url = get_mihomo_proxy_url()?;
+                builder = builder.proxy(Proxy::all(url.as_str())?);
+            }
         }
-        let r = builder
+
+        // Create request
+        let mut req = builder
             .build()?
-            .get(self.url.clone().unwrap())
-            .header("User-Agent", self.user_agent.clone().unwrap())
-            .send()
-            .await?;
-
-        // Parse header
-        let mut used = None;
-        let mut total = None;
-        let mut expired_at = None;
-        if let Some(h) = r.headers().get("Subscription-Userinfo") {
-            for seg in h.to_str()?.split(';') {
-                if let Some((k, v)) = seg.trim().split_once('=') {
-                    let v = match v.parse::<usize>() {
-                        Ok(v) => v,
-                        Err(_) => continue,
-                    };
-                    match k {
-                        "upload" => {
-                            used = if let Some(tmp) = used {
-                                Some(tmp + v)
-                            } else {
-                                Some(v)
-                            }
-                        }
-                        "download" => {
-                            used = if let Some(tmp) = used {
-                                Some(tmp + v)
-                            } else {
-                                Some(v)
-                            }
-                        }
-                        "total" => total = Some(v),
-                        "expire" => expired_at = Some(v as i64),
-                        _ => (),
-                    }
-                }
+            .get(
+                self.get_url()?
+                    .ok_or(anyhow!("profile URL cannot be empty"))?,
+            )
+            .header(USER_AGENT, self.user_agent.clone().unwrap());
+        let cfg = AppConfig::get_instance();
+        for (k, v) in &self.headers {
+            req = req.header(
+                k,
+                cfg.expand(v)
+                    .with_context(|| format!("try to expand header `{}`", k))?,
+            );
+        }
+        if let Some(auth) = &self.basic_auth {
+            req = req.basic_auth(
+                cfg.expand(&auth.username)
+                    .with_context(|| "try to expand basic auth")?,
+                Some(
+                    cfg.expand(&auth.password)
+                        .with_context(|| "try to expand basic auth")?,
+                ),
+            );
+        }
+        if let Some(token) = &self.bearer_token {
+            req = req.bearer_auth(
+                cfg.expand(token)
+                    .with_context(|| "try to expand bearer token")?,
+            );
+        }
+        if let Some(meta) = cached {
+            if let Some(etag) = &meta.etag {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                req = req.header(IF_MODIFIED_SINCE, last_modified);
             }
         }
 
-        // Save file
-        let path = path::get_profile_data_dir().join(format!("{}.yaml", self.uuid));
-        fs::write(&path, r.text().await?)?;
+        // Send request, URLs are stripped from errors since they may carry tokens
+        let r = req.send().await.map_err(reqwest::Error::without_url);
+        match r {
+            Ok(r) => Ok(r),
+            Err(err) if err.is_connect() && *proxy == ConfigProxy::Mihomo => Err(anyhow!(err))
+                .with_context(|| {
+                    format!(
+                        "Mihomo is unreachable at `{}`, make sure it is running",
+                        get_mihomo_proxy_url().map_or(String::new(), |v| v.to_string())
+                    )
+                }),
+            Err(err) => Err(err.into()),
+        }
+    }
 
-        // Success
-        Ok((used, total, expired_at))
+    /// Read profile data, empty if not fetched yet
+    ///
+    /// Data of a composite profile is composed from its member profiles,
+    /// and nodes of a profile with a template are spliced into the template
+    pub fn read_data(&self) -> Result<String> {
+        if self.r#type == ConfigType::Composite {
+            return composite::compose(self);
+        }
+
+        let path = self.get_data_path();
+        if path.is_file() {
+            template::splice(self, fs::read_to_string(&path)?)
+        } else if self.r#type == ConfigType::File {
+            bail!("profile data file `{}` not found", path.display());
+        } else {
+            Ok(String::new())
+        }
     }
 
-    pub async fn activate(&self) -> Result<()> {
+    /// Render the Mihomo config with all extends merged
+    pub fn render(&self) -> Result<String> {
         let cfg = AppConfig::get_instance();
 
         // Load data
-        let path = path::get_profile_data_dir().join(format!("{}.yaml", self.uuid));
-        let contents = if path.is_file() {
-            fs::read_to_string(&path)?
-        } else {
-            String::new()
-        };
+        let contents = self.read_data()?;
         let mut value = serde_yaml::from_str::<Value>(&contents)?;
 
         // Merge mihomosh configs
@@ -160,53 +465,401 @@ impl Config {
             merge_scripts("Global extend scripts", ext, &mut value)?;
         }
 
+        // Success
+        Ok(serde_yaml::to_string(&value)?)
+    }
+
+    /// Render, validate and write the Mihomo config, then restart Mihomo
+    ///
+    /// Returns the rendered config
+    pub async fn activate(
+        &self,
+        force: bool,
+        method: ConfigActivationMethod,
+        localize: bool,
+    ) -> Result<String> {
+        let cfg = AppConfig::get_instance();
+
+        // Render and validate data
+        let contents = self.render()?;
+        let problems = validate_rendered(&contents)?;
+        if !problems.is_empty() && !force {
+            bail!(
+                "rendered config has {} problems:\n{}",
+                problems.len(),
+                problems
+                    .iter()
+                    .map(|v| format!("  {}", v))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+
+        // Localize providers
+        let contents = if localize {
+            localize_providers(&contents)
+                .await
+                .with_context(|| "try to localize providers")?
+        } else {
+            contents
+        };
+
+        // Keep previous data for rollback
+        let previous = fs::read_to_string(&cfg.mihomo_path).ok();
+
         // Write data
-        fs::write(&cfg.mihomo_path, serde_yaml::to_string(&value)?)?;
+        fs::write(&cfg.mihomo_path, &contents)?;
 
-        // Restart mihomo
-        cfg.get_api().restart().await?;
+        // Apply data and wait for mihomo to come back, restarts are told apart since the old
+        // process keeps answering for a moment
+        let api = cfg.get_api()?;
+        let timeout = cfg.get_activation_timeout();
+        let duration = Duration::from_secs(timeout);
+        let applied = match method {
+            ConfigActivationMethod::Reload => match api.reload(&contents).await {
+                Ok(_) => Ok(false),
+                Err(err) if is_client_error(&err) => {
+                    // Mihomo keeps running the previous config, so only restore the file
+                    let failure = format!("Mihomo rejected the config: {:#}", err);
+                    let Some(previous) = previous else {
+                        bail!(failure);
+                    };
+                    fs::write(&cfg.mihomo_path, previous)?;
+                    bail!("{}, previous config restored", failure)
+                }
+                Err(err) => {
+                    println!(
+                        "{}",
+                        console::style(format!(
+                            "Fail to reload Mihomo, fallback to restart: {:#}",
+                            err
+                        ))
+                        .yellow()
+                    );
+                    api.restart().await.map(|_| true)
+                }
+            },
+            ConfigActivationMethod::Restart => api.restart().await.map(|_| true),
+        };
+        let failure = match applied {
+            Ok(true) if api.wait_restarted(duration).await => return Ok(contents),
+            Ok(false) if api.wait_healthy(duration).await => return Ok(contents),
+            Ok(_) => format!("Mihomo did not come back within {}s", timeout),
+            Err(err) => format!("fail to restart Mihomo: {:#}", err),
+        };
 
-        // Success
-        Ok(())
+        // Rollback to previous data
+        let Some(previous) = previous else {
+            bail!(failure);
+        };
+        fs::write(&cfg.mihomo_path, previous)?;
+        let restored = api.restart().await.is_ok() && api.wait_restarted(duration).await;
+        if !restored {
+            bail!(
+                "{}, previous config restored but Mihomo is still unhealthy",
+                failure
+            );
+        }
+        bail!("{}, previous config restored", failure)
     }
 }
 
-#[derive(Clone, Deserialize, PartialEq)]
+/// Result of fetching a remote profile
+#[derive(Default)]
+pub struct FetchResult {
+    /// Whether the server responded `304 Not Modified`
+    pub unchanged: bool,
+
+    pub used_bytes: Option<usize>,
+    pub upload_bytes: Option<usize>,
+    pub download_bytes: Option<usize>,
+    pub total_bytes: Option<usize>,
+    pub expired_at: Option<i64>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+
+    /// Provider suggested update interval in minutes
+    pub update_interval: Option<u64>,
+
+    pub web_page_url: Option<String>,
+
+    /// Provider suggested file name from `Content-Disposition`
+    pub file_name: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ConfigBasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ConfigType {
     Local,
     Remote,
+    File,
+    Composite,
+}
+impl Display for ConfigType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Remote => write!(f, "remote"),
+            Self::File => write!(f, "file"),
+            Self::Composite => write!(f, "composite"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigPool {
+    /// Copy member nodes with profile name prefixed
+    #[default]
+    Merge,
+
+    /// Expose member nodes as inline proxy providers
+    Provider,
 }
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ConfigProxy {
+    #[default]
     None,
     System,
     Mihomo,
 }
-impl Default for ConfigProxy {
-    fn default() -> Self {
-        Self::None
+
+fn verify_data<S: AsRef<str>>(contents: S) -> Result<()> {
+    let value = serde_yaml::from_str::<Value>(contents.as_ref())?;
+    if !value.is_mapping() {
+        bail!("profile data must be an object");
+    }
+
+    // Need at least one proxy or proxy provider
+    let has_proxies = value
+        .get("proxies")
+        .and_then(Value::as_sequence)
+        .is_some_and(|v| !v.is_empty());
+    let has_providers = value
+        .get("proxy-providers")
+        .and_then(Value::as_mapping)
+        .is_some_and(|v| !v.is_empty());
+    if !has_proxies && !has_providers {
+        bail!("profile data contains no proxies");
+    }
+
+    Ok(())
+}
+
+fn parse_headers(r: &Response) -> FetchResult {
+    let mut result = FetchResult {
+        unchanged: r.status() == StatusCode::NOT_MODIFIED,
+        etag: get_header(r, ETAG),
+        last_modified: get_header(r, LAST_MODIFIED),
+        update_interval: get_header(r, "Profile-Update-Interval")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(|v| v * 60),
+        web_page_url: get_header(r, "Profile-Web-Page-Url"),
+        file_name: get_header(r, CONTENT_DISPOSITION).and_then(parse_file_name),
+        ..Default::default()
+    };
+    if let Some(h) = get_header(r, "Subscription-Userinfo") {
+        for seg in h.split(';') {
+            if let Some((k, v)) = seg.trim().split_once('=') {
+                let v = match v.trim().parse::<usize>() {
+                    Ok(v) => v,
+                    Err(_) => continue,
+                };
+                match k.trim() {
+                    "upload" => result.upload_bytes = Some(v),
+                    "download" => result.download_bytes = Some(v),
+                    "total" => result.total_bytes = Some(v),
+                    "expire" => result.expired_at = Some(v as i64),
+                    _ => (),
+                }
+            }
+        }
+    }
+    if result.upload_bytes.is_some() || result.download_bytes.is_some() {
+        result.used_bytes =
+            Some(result.upload_bytes.unwrap_or(0) + result.download_bytes.unwrap_or(0));
+    }
+
+    result
+}
+
+fn is_transient(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .is_some_and(|v| v.is_timeout() || v.is_connect() || v.is_body())
+}
+
+/// Whether an error is a `4xx` response, i.e. retrying the same request would not help
+fn is_client_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(reqwest::Error::status)
+        .is_some_and(|v| v.is_client_error())
+}
+
+fn parse_file_name(disposition: String) -> Option<String> {
+    let mut plain = None;
+    for seg in disposition.split(';') {
+        let (k, v) = match seg.trim().split_once('=') {
+            Some(v) => v,
+            None => continue,
+        };
+        let v = v.trim().trim_matches('"');
+        match k.trim().to_lowercase().as_str() {
+            // RFC 5987 extended value, e.g. `UTF-8''%E4%B8%AD`
+            "filename*" => {
+                let v = v.split_once("''").map_or(v, |(_, v)| v);
+                return urlencoding::decode(v).ok().map(|v| v.into_owned());
+            }
+            "filename" => plain = Some(v.to_owned()),
+            _ => (),
+        }
     }
+
+    plain.filter(|v| !v.is_empty())
 }
 
+fn get_header<K: AsHeaderName>(r: &Response, name: K) -> Option<String> {
+    r.headers()
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned)
+}
+
+fn get_mihomo_proxy_url() -> Result<Url> {
+    let cfg = AppConfig::get_instance();
+
+    // Mihomo serves HTTP proxy on its mixed port
+    let host = cfg.mihomo_api.host_str().unwrap_or("localhost");
+    Ok(Url::parse(&format!("http://{}:{}", host, cfg.mixed_port))?)
+}
+
+/// Merge directive parsed from a top-level mapping key
+enum MergeDirective<'a> {
+    /// `prepend-key` or `+key`
+    Prepend(&'a str),
+
+    /// `append-key` or `key+`
+    Append(&'a str),
+
+    /// `key!`
+    Override(&'a str),
+
+    /// Plain key
+    Merge,
+}
+impl<'a> MergeDirective<'a> {
+    fn parse(key: &'a str) -> Self {
+        let directive = if let Some(v) = key.strip_prefix("prepend-") {
+            Self::Prepend(v)
+        } else if let Some(v) = key.strip_prefix("append-") {
+            Self::Append(v)
+        } else if let Some(v) = key.strip_prefix('+') {
+            Self::Prepend(v)
+        } else if let Some(v) = key.strip_suffix('+') {
+            Self::Append(v)
+        } else if let Some(v) = key.strip_suffix('!') {
+            Self::Override(v)
+        } else {
+            Self::Merge
+        };
+
+        match directive {
+            Self::Prepend("") | Self::Append("") | Self::Override("") => Self::Merge,
+            v => v,
+        }
+    }
+}
+
+/// Sequences whose items are matched by name when prepending or appending
+const NAMED_SEQUENCES: [&str; 2] = ["proxies", "proxy-groups"];
+
+/// Merge extend configs, applying directives of top-level keys only
+///
+/// Nested keys like `+.example.com` in `hosts` are kept as they are.
 fn merge_yaml(src: &Value, dst: &mut Value) {
+    let (Value::Mapping(src), Value::Mapping(dst)) = (src, &mut *dst) else {
+        return merge_value(src, dst);
+    };
+
+    for (k, v) in src {
+        match MergeDirective::parse(k.as_str().unwrap_or_default()) {
+            MergeDirective::Prepend(key) => merge_sequence(v, dst, key, true),
+            MergeDirective::Append(key) => merge_sequence(v, dst, key, false),
+            MergeDirective::Override(key) => {
+                dst.insert(key.into(), v.clone());
+            }
+            MergeDirective::Merge => merge_entry(k, v, dst),
+        }
+    }
+}
+
+/// Merge mappings deeply and replace other values
+fn merge_value(src: &Value, dst: &mut Value) {
     match (src, dst) {
-        (Value::Mapping(src), dst @ &mut Value::Mapping(_)) => {
-            let dst = dst.as_mapping_mut().unwrap();
+        (Value::Mapping(src), Value::Mapping(dst)) => {
             for (k, v) in src {
-                if !dst.contains_key(k) {
-                    dst.insert(k.clone(), v.clone());
-                } else {
-                    merge_yaml(v, &mut dst[&k]);
-                }
+                merge_entry(k, v, dst);
             }
         }
         (src, dst) => *dst = src.clone(),
     }
 }
 
+/// Merge a mapping entry, deleting the key if the value is null
+fn merge_entry(k: &Value, v: &Value, dst: &mut Mapping) {
+    if v.is_null() {
+        dst.remove(k);
+        return;
+    }
+
+    match dst.get_mut(k) {
+        Some(d) => merge_value(v, d),
+        None => {
+            dst.insert(k.clone(), v.clone());
+        }
+    }
+}
+
+fn merge_sequence(src: &Value, dst: &mut Mapping, key: &str, prepend: bool) {
+    let (Some(items), Some(list)) = (
+        src.as_sequence(),
+        dst.get_mut(key).and_then(Value::as_sequence_mut),
+    ) else {
+        dst.insert(key.into(), src.clone());
+        return;
+    };
+
+    // Replace items with the same name
+    let get_name = |v: &Value| v.get("name").and_then(Value::as_str).map(str::to_owned);
+    let mut rest = Vec::new();
+    for item in items {
+        let idx = match get_name(item) {
+            Some(name) if NAMED_SEQUENCES.contains(&key) => list
+                .iter()
+                .position(|v| get_name(v).as_ref() == Some(&name)),
+            _ => None,
+        };
+        match idx {
+            Some(idx) => list[idx] = item.clone(),
+            None => rest.push(item.clone()),
+        }
+    }
+
+    // Add the other items
+    if prepend {
+        list.splice(0..0, rest);
+    } else {
+        list.extend(rest);
+    }
+}
+
 fn merge_scripts<S1, S2>(name: S1, scripts: S2, dst: &mut Value) -> Result<()>
 where
     S1: AsRef<str>,
@@ -271,3 +924,177 @@ where
     *dst = serde_json::from_str(&result)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(src: &str, dst: &str) -> Value {
+        let mut value = serde_yaml::from_str(dst).unwrap();
+        merge_yaml(&serde_yaml::from_str(src).unwrap(), &mut value);
+        value
+    }
+
+    fn yaml(contents: &str) -> Value {
+        serde_yaml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn merge_yaml_keeps_nested_keys() {
+        let value = merge(
+            r#"
+hosts:
+  "+.lan": 192.168.1.1
+  "example.com!": 1.1.1.1
+dns:
+  nameserver-policy:
+    "+.example.com": 8.8.8.8
+    "geosite:cn+": 223.5.5.5
+"#,
+            r#"
+hosts:
+  localhost: 127.0.0.1
+dns:
+  enable: true
+  nameserver-policy:
+    "+.example.com": 1.1.1.1
+"#,
+        );
+        let expected = yaml(
+            r#"
+hosts:
+  localhost: 127.0.0.1
+  "+.lan": 192.168.1.1
+  "example.com!": 1.1.1.1
+dns:
+  enable: true
+  nameserver-policy:
+    "+.example.com": 8.8.8.8
+    "geosite:cn+": 223.5.5.5
+"#,
+        );
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn merge_yaml_directives() {
+        let value = merge(
+            r#"
+prepend-rules:
+  - DOMAIN,a.com,DIRECT
+rules+:
+  - MATCH,PROXY
++proxies:
+  - {name: b, type: ss, port: 2}
+  - {name: c, type: ss, port: 3}
+append-proxy-groups:
+  - {name: PROXY, type: select, proxies: [b, c]}
+  - {name: Auto, type: url-test, proxies: [b]}
+dns!:
+  enable: false
+"#,
+            r#"
+proxies:
+  - {name: a, type: ss, port: 1}
+  - {name: b, type: ss, port: 1}
+proxy-groups:
+  - {name: PROXY, type: select, proxies: [a]}
+rules:
+  - DOMAIN,b.com,PROXY
+dns:
+  enable: true
+  ipv6: true
+"#,
+        );
+        let expected = yaml(
+            r#"
+proxies:
+  - {name: c, type: ss, port: 3}
+  - {name: a, type: ss, port: 1}
+  - {name: b, type: ss, port: 2}
+proxy-groups:
+  - {name: PROXY, type: select, proxies: [b, c]}
+  - {name: Auto, type: url-test, proxies: [b]}
+rules:
+  - DOMAIN,a.com,DIRECT
+  - DOMAIN,b.com,PROXY
+  - MATCH,PROXY
+dns:
+  enable: false
+"#,
+        );
+        assert_eq!(value, expected);
+
+        // Missing sequences are inserted
+        let value = merge("append-rules: [MATCH,DIRECT]", "{}");
+        assert_eq!(value, yaml("rules: [MATCH,DIRECT]"));
+    }
+
+    #[test]
+    fn merge_yaml_deletes_null() {
+        let value = merge(
+            "tun: null
+dns:
+  fallback: null
+  enable: true
+",
+            "tun: {enable: true}
+dns:
+  fallback: [1.1.1.1]
+  ipv6: true
+",
+        );
+        assert_eq!(
+            value,
+            yaml(
+                "dns:
+  ipv6: true
+  enable: true
+"
+            )
+        );
+    }
+
+    #[test]
+    fn redact_contents() {
+        let contents = r#"# Header: 1
+name: My 1
+type: remote
+url: https://example.com/sub/abcdefghijklmn?token=s3cr3t-t0k3n&v=1
+user_agent: clash/1
+headers:
+  X-Version: 1
+  "Accept": application/json # note
+basic_auth:
+  username: user
+  password: |
+    multi
+    line
+bearer_token: 1
+extend_configs:
+  password: plain
+"#;
+        let expected = r#"# Header: 1
+name: My 1
+type: remote
+url: https://example.com/sub/******?token=******&v=1
+user_agent: clash/1
+headers:
+  X-Version: ******
+  "Accept": ******
+basic_auth:
+  username: user
+  password: ******
+    ******
+    ******
+bearer_token: ******
+extend_configs:
+  password: plain
+"#;
+        assert_eq!(Config::redact_contents(contents), expected);
+
+        let contents = "headers: {X-Token: abc}\nbasic_auth: {username: u, password: p}\n";
+        let expected = "headers: ******\nbasic_auth: ******\n";
+        assert_eq!(Config::redact_contents(contents), expected);
+    }
+}
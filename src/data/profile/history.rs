@@ -0,0 +1,252 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::config::Config as AppConfig,
+    utils::{file, path},
+};
+
+/// Snapshot of a profile's config and data files
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Revision {
+    #[serde(skip)]
+    dir: PathBuf,
+
+    #[serde(skip)]
+    pub rev: usize,
+
+    pub action: String,
+    pub created_at: i64,
+}
+impl Revision {
+    fn get_dir(&self) -> PathBuf {
+        self.dir.join(self.rev.to_string())
+    }
+
+    pub fn read_conf(&self) -> Result<Option<String>> {
+        read_optional(self.get_dir().join("conf.yaml"))
+    }
+
+    pub fn read_data(&self) -> Result<Option<String>> {
+        read_optional(self.get_dir().join("data.yaml"))
+    }
+
+    pub fn get_styled_created_at(&self) -> String {
+        Utc.timestamp_opt(self.created_at, 0)
+            .unwrap()
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    }
+}
+
+/// Profile snapshots history
+pub struct History {
+    dir: PathBuf,
+    conf: PathBuf,
+    data: PathBuf,
+}
+impl History {
+    pub fn new<S: AsRef<str>>(uuid: S) -> Self {
+        let uuid = uuid.as_ref();
+        Self {
+            dir: path::get_profile_history_dir().join(uuid),
+            conf: path::get_profile_conf_dir().join(format!("{}.yaml", uuid)),
+            data: path::get_profile_data_dir().join(format!("{}.yaml", uuid)),
+        }
+    }
+
+    /// List revisions in ascending order
+    pub fn list(&self) -> Result<Vec<Revision>> {
+        let dir = &self.dir;
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut revs = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let rev = match entry.file_name().to_str().and_then(|v| v.parse().ok()) {
+                Some(v) => v,
+                None => continue,
+            };
+            let path = entry.path().join("revision.json");
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("try to read file `{}`", path.display()))?;
+            let value = serde_json::from_str::<Revision>(&contents)
+                .with_context(|| format!("try to parse file `{}`", path.display()))?;
+            revs.push(Revision {
+                dir: dir.clone(),
+                rev,
+                ..value
+            });
+        }
+        revs.sort_by_key(|v| v.rev);
+
+        Ok(revs)
+    }
+
+    /// Get revision by number
+    pub fn get(&self, rev: usize) -> Result<Revision> {
+        self.list()?
+            .into_iter()
+            .find(|v| v.rev == rev)
+            .ok_or(anyhow!("revision `{}` not found", rev))
+    }
+
+    /// Snapshot current config and data files
+    pub fn snapshot<S: AsRef<str>>(&self, action: S) -> Result<()> {
+        self.snapshot_with_limit(action, AppConfig::get_instance().get_history_limit())
+    }
+
+    /// Snapshot current config and data files, keeping at most `limit` revisions
+    fn snapshot_with_limit<S: AsRef<str>>(&self, action: S, limit: usize) -> Result<()> {
+        if limit == 0 {
+            return Ok(());
+        }
+
+        // Create revision directory
+        let revs = self.list()?;
+        let rev = Revision {
+            dir: self.dir.clone(),
+            rev: revs.last().map_or(1, |v| v.rev + 1),
+            action: action.as_ref().to_owned(),
+            created_at: Utc::now().timestamp(),
+        };
+        let dir = rev.get_dir();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("try to create directory `{}`", dir.display()))?;
+
+        // Copy files
+        for (src, name) in [(&self.conf, "conf.yaml"), (&self.data, "data.yaml")] {
+            if src.is_file() {
+                fs::copy(src, dir.join(name))
+                    .with_context(|| format!("try to copy file `{}`", src.display()))?;
+            }
+        }
+        let path = dir.join("revision.json");
+        file::write_private(&path, serde_json::to_string(&rev)?)
+            .with_context(|| format!("try to write file `{}`", path.display()))?;
+
+        // Prune old revisions
+        let count = revs.len() + 1;
+        if count > limit {
+            for old in revs.iter().take(count - limit) {
+                let dir = old.get_dir();
+                fs::remove_dir_all(&dir)
+                    .with_context(|| format!("try to delete directory `{}`", dir.display()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Snapshot current files as the initial revision before they are overwritten,
+    /// if the profile has no revisions yet
+    pub fn snapshot_initial(&self) -> Result<()> {
+        if !self.list()?.is_empty() {
+            return Ok(());
+        }
+
+        self.snapshot("initial")
+    }
+
+    /// Restore config and data files from a revision
+    pub fn restore(&self, rev: usize) -> Result<()> {
+        let rev = self.get(rev)?;
+
+        // Restore config file
+        let conf = rev
+            .read_conf()?
+            .ok_or(anyhow!("revision `{}` has no config", rev.rev))?;
+        let path = &self.conf;
+        file::write_private(path, conf)
+            .with_context(|| format!("try to write file `{}`", path.display()))?;
+
+        // Restore data file
+        let path = &self.data;
+        match rev.read_data()? {
+            Some(data) => file::write_private(path, data)
+                .with_context(|| format!("try to write file `{}`", path.display()))?,
+            None if path.is_file() => fs::remove_file(path)
+                .with_context(|| format!("try to delete file `{}`", path.display()))?,
+            None => (),
+        }
+
+        Ok(())
+    }
+
+    /// Remove all revisions
+    pub fn remove(&self) -> Result<()> {
+        let dir = &self.dir;
+        if dir.is_dir() {
+            fs::remove_dir_all(dir)
+                .with_context(|| format!("try to delete directory `{}`", dir.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_optional(path: PathBuf) -> Result<Option<String>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    Ok(Some(fs::read_to_string(&path).with_context(|| {
+        format!("try to read file `{}`", path.display())
+    })?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(root: &tempfile::TempDir) -> History {
+        History {
+            dir: root.path().join("history"),
+            conf: root.path().join("conf.yaml"),
+            data: root.path().join("data.yaml"),
+        }
+    }
+
+    #[test]
+    fn prune_old_revisions() {
+        let root = tempfile::tempdir().unwrap();
+        let history = history(&root);
+        fs::write(&history.conf, "name: a").unwrap();
+        for action in ["create", "update", "edit", "update"] {
+            history.snapshot_with_limit(action, 2).unwrap();
+        }
+
+        let revs = history.list().unwrap();
+        assert_eq!(revs.iter().map(|v| v.rev).collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(revs[0].action, "edit");
+        assert!(!history.dir.join("1").exists());
+
+        history.snapshot_with_limit("update", 0).unwrap();
+        assert_eq!(history.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn restore_revision() {
+        let root = tempfile::tempdir().unwrap();
+        let history = history(&root);
+        fs::write(&history.conf, "name: a").unwrap();
+        history.snapshot_with_limit("create", 10).unwrap();
+        fs::write(&history.conf, "name: b").unwrap();
+        fs::write(&history.data, "rules: []").unwrap();
+        history.snapshot_with_limit("update", 10).unwrap();
+
+        history.restore(1).unwrap();
+        assert_eq!(fs::read_to_string(&history.conf).unwrap(), "name: a");
+        assert!(!history.data.exists());
+
+        history.restore(2).unwrap();
+        assert_eq!(fs::read_to_string(&history.conf).unwrap(), "name: b");
+        assert_eq!(fs::read_to_string(&history.data).unwrap(), "rules: []");
+        assert!(history.restore(3).is_err());
+    }
+}
@@ -4,13 +4,14 @@ use anyhow::Result;
 use chrono::{TimeZone, Utc};
 use console::StyledObject;
 use serde::{Deserialize, Serialize};
+use url::Url;
 
 use crate::utils::{self, path};
 
-use super::Config;
+use super::{Config, FetchResult};
 
 /// Metadata
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct Meta {
     #[serde(skip)]
     pub uuid: String,
@@ -20,18 +21,76 @@ pub struct Meta {
     pub updated_at: Option<i64>,
     pub expired_at: Option<i64>,
     pub used_bytes: Option<usize>,
+    pub upload_bytes: Option<usize>,
+    pub download_bytes: Option<usize>,
     pub total_bytes: Option<usize>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub update_interval: Option<u64>,
+    pub web_page_url: Option<String>,
+    pub file_name: Option<String>,
 }
 impl Meta {
     pub fn try_get_conf(&self) -> Result<Config> {
         let path = path::get_profile_conf_dir().join(format!("{}.yaml", self.uuid));
         let contents = fs::read_to_string(&path)?;
-        let mut value = serde_yaml::from_str::<Config>(&contents)?;
+        let mut value = Config::from_str(&contents)?;
         value.uuid = self.uuid.clone();
 
         Ok(value)
     }
 
+    /// Get web page URL with secret parts redacted
+    pub fn get_redacted_web_page_url(&self) -> Option<String> {
+        let url = self.web_page_url.as_ref()?;
+
+        Some(Url::parse(url).map_or(url.clone(), |v| utils::redact_url(&v)))
+    }
+
+    pub fn apply_fetch_result(&mut self, r: FetchResult) {
+        self.updated_at = Some(Utc::now().timestamp());
+
+        // Keep cached values if the server omits them on `304 Not Modified`
+        if r.unchanged {
+            self.used_bytes = r.used_bytes.or(self.used_bytes);
+            self.upload_bytes = r.upload_bytes.or(self.upload_bytes);
+            self.download_bytes = r.download_bytes.or(self.download_bytes);
+            self.total_bytes = r.total_bytes.or(self.total_bytes);
+            self.expired_at = r.expired_at.or(self.expired_at);
+            self.etag = r.etag.or(self.etag.take());
+            self.last_modified = r.last_modified.or(self.last_modified.take());
+            self.update_interval = r.update_interval.or(self.update_interval);
+            self.web_page_url = r.web_page_url.or(self.web_page_url.take());
+            self.file_name = r.file_name.or(self.file_name.take());
+        } else {
+            self.used_bytes = r.used_bytes;
+            self.upload_bytes = r.upload_bytes;
+            self.download_bytes = r.download_bytes;
+            self.total_bytes = r.total_bytes;
+            self.expired_at = r.expired_at;
+            self.etag = r.etag;
+            self.last_modified = r.last_modified;
+            self.update_interval = r.update_interval;
+            self.web_page_url = r.web_page_url;
+            self.file_name = r.file_name;
+        }
+    }
+
+    /// Get provider suggested profile name
+    pub fn get_suggested_name(&self) -> Option<String> {
+        let name = self.file_name.as_ref()?;
+        let name = name
+            .strip_suffix(".yaml")
+            .or(name.strip_suffix(".yml"))
+            .unwrap_or(name)
+            .trim();
+        if name.is_empty() {
+            None
+        } else {
+            Some(name.to_owned())
+        }
+    }
+
     pub fn get_styled_name(&self) -> String {
         if self.name.len() > 16 {
             let mut tmp = self.name[..13].to_string();
@@ -95,9 +154,8 @@ impl Meta {
             return console::style("N/A".into()).bright().black();
         }
 
-        if self.used_bytes.is_some() && self.total_bytes.is_some() {
-            let percent =
-                self.used_bytes.unwrap() as f64 / self.total_bytes.unwrap() as f64 * 100.0;
+        if let (Some(used), Some(total)) = (self.used_bytes, self.total_bytes) {
+            let percent = used as f64 / total as f64 * 100.0;
             let percent = if percent < 70.0 {
                 console::style(format!("({:.1}%)", percent)).green()
             } else if percent < 90.0 {
@@ -108,8 +166,8 @@ impl Meta {
 
             return console::style(format!(
                 "{}/{} {}",
-                utils::get_size_str(self.used_bytes.unwrap()),
-                utils::get_size_str(self.total_bytes.unwrap()),
+                utils::get_size_str(used),
+                utils::get_size_str(total),
                 percent
             ));
         }
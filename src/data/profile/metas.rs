@@ -9,7 +9,7 @@ use std::{
 use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 
-use crate::utils::path;
+use crate::utils::{file, path};
 
 use super::Meta;
 
@@ -21,35 +21,39 @@ impl Metas {
         path::get_data_dir().join("profile_metas.json")
     }
 
+    /// Load from metadata file
+    fn load() -> Result<Self> {
+        let path = Self::get_path();
+        if !path.is_file() {
+            return Ok(Self(HashMap::new()));
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("try to read file `{}`", path.display()))?;
+        let value = serde_json::from_str::<HashMap<String, Meta>>(&contents)
+            .with_context(|| format!("try to parse file `{}`", path.display()))?
+            .into_iter()
+            .map(|(k, v)| (k.clone(), Meta { uuid: k, ..v }))
+            .collect::<HashMap<_, _>>();
+
+        Ok(Self(value))
+    }
+
     /// Get global instance
     pub fn get_instance() -> &'static Mutex<Self> {
         static I: OnceLock<Mutex<Metas>> = OnceLock::new();
-        I.get_or_init(|| {
-            let path = Self::get_path();
-            if path.is_file() {
-                let contents = fs::read_to_string(&path)
-                    .with_context(|| format!("try to read file `{}`", path.display()))
-                    .unwrap();
-                let value = serde_json::from_str::<HashMap<String, Meta>>(&contents)
-                    .with_context(|| format!("try to parse file `{}`", path.display()))
-                    .unwrap()
-                    .into_iter()
-                    .map(|(k, v)| (k.clone(), Meta { uuid: k, ..v }))
-                    .collect::<HashMap<_, _>>();
+        I.get_or_init(|| Mutex::new(Self::load().unwrap()))
+    }
 
-                Mutex::new(Self(value))
-            } else {
-                Mutex::new(Self(HashMap::new()))
-            }
-        })
+    /// Reload from metadata file
+    pub fn reload(&mut self) -> Result<()> {
+        *self = Self::load()?;
+        Ok(())
     }
 
     /// Flush to metadata file
     pub fn flush(&self) -> Result<()> {
-        Ok(fs::write(
-            Self::get_path(),
-            serde_json::to_string(&self.0)?,
-        )?)
+        file::write_private(Self::get_path(), serde_json::to_string(&self.0)?)
     }
 
     /// Try to get metadata by uuid or name
@@ -73,7 +77,7 @@ impl Metas {
                 bail!("multiple found");
             }
 
-            Ok(candidates.get(0).unwrap())
+            Ok(candidates.first().unwrap())
         }
     }
 }
@@ -1,7 +1,26 @@
+mod bundle;
+mod composite;
 mod config;
+mod history;
 mod meta;
 mod metas;
+mod providers;
+mod share_link;
+mod template;
+mod validate;
+mod verge;
 
+pub use bundle::*;
 pub use config::*;
+pub use history::*;
 pub use meta::*;
 pub use metas::*;
+pub use providers::*;
+pub use validate::*;
+pub use verge::*;
+
+/// URL used by generated url-test groups and provider health checks
+const URL_TEST_URL: &str = "https://www.gstatic.com/generate_204";
+
+/// Interval in seconds of generated url-test groups and provider health checks
+const URL_TEST_INTERVAL: u64 = 300;
@@ -0,0 +1,428 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use reqwest::{header::USER_AGENT, Client, Response};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use url::Url;
+
+use crate::{
+    data::config::Config as AppConfig,
+    utils::{self, file},
+};
+
+/// Provider sections whose `http` providers are localized, with their kinds in Mihomo API
+const PROVIDER_SECTIONS: [(&str, &str); 2] =
+    [("rule-providers", "rules"), ("proxy-providers", "proxies")];
+
+/// Provider fields only used by Mihomo to download
+const DOWNLOAD_FIELDS: [&str; 4] = ["url", "interval", "proxy", "header"];
+
+/// Name of the cache directory next to the Mihomo config
+const CACHE_DIR_NAME: &str = "mihomosh_providers";
+
+/// Name of the index file mapping cached files to their sources
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// User agent used when a provider does not set one, the same as Mihomo
+const DEFAULT_USER_AGENT: &str = "clash.meta";
+
+/// Source of a cached provider file
+#[derive(Deserialize, Serialize)]
+struct Source {
+    url: String,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    header: BTreeMap<String, Vec<String>>,
+}
+
+/// Get the provider cache directory
+///
+/// Mihomo only reads provider files inside its home directory,
+/// so the cache lives next to the Mihomo config
+pub fn get_provider_cache_dir() -> PathBuf {
+    Path::new(&AppConfig::get_instance().mihomo_path)
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(CACHE_DIR_NAME)
+}
+
+/// Download `http` rule and proxy providers into the cache and rewrite them to `file` providers
+///
+/// Cached files are reused, providers which cannot be downloaded are kept untouched
+pub async fn localize_providers<S: AsRef<str>>(contents: S) -> Result<String> {
+    let mut value = serde_yaml::from_str::<Value>(contents.as_ref())?;
+    let dir = get_provider_cache_dir();
+    let mut index = load_index(&dir)?;
+
+    for (section, _) in PROVIDER_SECTIONS {
+        let Some(providers) = value.get_mut(section).and_then(Value::as_mapping_mut) else {
+            continue;
+        };
+        for (name, provider) in providers.iter_mut() {
+            let Some((source, file_name)) = get_source(provider) else {
+                continue;
+            };
+
+            // Download if not cached yet
+            let path = dir.join(&file_name);
+            if !path.is_file() {
+                if let Err(err) = download(&source, &path).await {
+                    println!(
+                        "{}",
+                        console::style(format!(
+                            "Provider `{}` fail to download, leave it to Mihomo: {:#}",
+                            name.as_str().unwrap_or_default(),
+                            err
+                        ))
+                        .yellow()
+                    );
+                    continue;
+                }
+            }
+            index.insert(file_name, source);
+
+            // Rewrite provider
+            rewrite_provider(provider, &path);
+        }
+    }
+
+    // Save index
+    save_index(&dir, &index).with_context(|| "try to save provider cache index")?;
+
+    // Success
+    Ok(serde_yaml::to_string(&value)?)
+}
+
+/// Get the source and cache file name of an `http` provider
+fn get_source(provider: &Value) -> Option<(Source, String)> {
+    if provider.get("type").and_then(Value::as_str) != Some("http") {
+        return None;
+    }
+    let url = provider.get("url").and_then(Value::as_str)?;
+    let source = Source {
+        url: url.to_owned(),
+        header: provider
+            .get("header")
+            .and_then(|v| serde_yaml::from_value(v.clone()).ok())
+            .unwrap_or_default(),
+    };
+
+    let ext = match provider.get("format").and_then(Value::as_str) {
+        Some("mrs") => "mrs",
+        Some("text") => "txt",
+        _ => "yaml",
+    };
+    Some((source, format!("{}.{}", &utils::hash(url)[..16], ext)))
+}
+
+/// Rewrite a provider to a `file` one reading the cached file
+fn rewrite_provider(provider: &mut Value, path: &Path) {
+    let Some(provider) = provider.as_mapping_mut() else {
+        return;
+    };
+    provider.insert("type".into(), "file".into());
+    provider.insert("path".into(), path.to_string_lossy().as_ref().into());
+    for key in DOWNLOAD_FIELDS {
+        provider.remove(key);
+    }
+}
+
+/// Download all cached providers again and reload them in Mihomo
+///
+/// Cached files no longer used by the current Mihomo config are deleted first.
+/// Returns the number of refreshed providers, ones which fail to download keep their cached files
+pub async fn refresh_providers() -> Result<usize> {
+    let dir = get_provider_cache_dir();
+    let mut index = load_index(&dir)?;
+
+    // Prune unused files, profiles activated later download their providers again
+    let current = fs::read_to_string(&AppConfig::get_instance().mihomo_path).ok();
+    if let Some(contents) = current {
+        let used = get_file_providers(&contents)?
+            .into_iter()
+            .map(|(_, _, path)| path)
+            .collect::<HashSet<_>>();
+        prune_index(&dir, &mut index, &used).with_context(|| "try to prune provider cache")?;
+        save_index(&dir, &index).with_context(|| "try to save provider cache index")?;
+    }
+
+    // Download
+    let mut refreshed = HashSet::new();
+    for (file_name, source) in &index {
+        let path = dir.join(file_name);
+        match download(source, &path).await {
+            Ok(()) => {
+                refreshed.insert(path);
+            }
+            Err(err) => println!(
+                "{}",
+                console::style(format!(
+                    "Provider `{}` fail to refresh, keep the cached file: {:#}",
+                    get_display_url(&source.url),
+                    err
+                ))
+                .yellow()
+            ),
+        }
+    }
+
+    // Reload refreshed providers of the current Mihomo config
+    if !refreshed.is_empty() {
+        reload_providers(&refreshed)
+            .await
+            .with_context(|| "try to reload refreshed providers in Mihomo")?;
+    }
+
+    Ok(refreshed.len())
+}
+
+/// Reload providers of the current Mihomo config whose files are in the given paths
+async fn reload_providers(paths: &HashSet<PathBuf>) -> Result<()> {
+    let cfg = AppConfig::get_instance();
+    let Ok(contents) = fs::read_to_string(&cfg.mihomo_path) else {
+        return Ok(());
+    };
+
+    let api = cfg.get_api()?;
+    for (kind, name, path) in get_file_providers(&contents)? {
+        if !paths.contains(&path) {
+            continue;
+        }
+        api.update_provider(kind, &name)
+            .await
+            .with_context(|| format!("try to reload provider `{}`", name))?;
+    }
+
+    Ok(())
+}
+
+/// Get `file` providers of a Mihomo config, with their kinds in Mihomo API, names and paths
+fn get_file_providers(contents: &str) -> Result<Vec<(&'static str, String, PathBuf)>> {
+    let value = serde_yaml::from_str::<Value>(contents)?;
+
+    let mut providers = Vec::new();
+    for (section, kind) in PROVIDER_SECTIONS {
+        let Some(mapping) = value.get(section).and_then(Value::as_mapping) else {
+            continue;
+        };
+        for (name, provider) in mapping {
+            if provider.get("type").and_then(Value::as_str) != Some("file") {
+                continue;
+            }
+            let Some(path) = provider.get("path").and_then(Value::as_str) else {
+                continue;
+            };
+            let name = name.as_str().unwrap_or_default().to_owned();
+            providers.push((kind, name, PathBuf::from(path)));
+        }
+    }
+
+    Ok(providers)
+}
+
+/// Delete cached files not in the used paths, along with their index entries
+fn prune_index(
+    dir: &Path,
+    index: &mut BTreeMap<String, Source>,
+    used: &HashSet<PathBuf>,
+) -> Result<()> {
+    let unused = index
+        .keys()
+        .filter(|v| !used.contains(&dir.join(v)))
+        .cloned()
+        .collect::<Vec<_>>();
+    for file_name in unused {
+        index.remove(&file_name);
+        let path = dir.join(&file_name);
+        if path.is_file() {
+            fs::remove_file(&path)
+                .with_context(|| format!("try to delete file `{}`", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn load_index(dir: &Path) -> Result<BTreeMap<String, Source>> {
+    let path = dir.join(INDEX_FILE_NAME);
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("try to parse provider cache index `{}`", path.display()))
+}
+
+fn save_index(dir: &Path, index: &BTreeMap<String, Source>) -> Result<()> {
+    if index.is_empty() && !dir.is_dir() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(dir)?;
+    file::write_atomic(dir.join(INDEX_FILE_NAME), serde_json::to_string(index)?)
+}
+
+async fn download(source: &Source, path: &Path) -> Result<()> {
+    // Create request
+    let timeout = AppConfig::get_instance().get_fetch_timeout();
+    let mut req = Client::builder()
+        .timeout(Duration::from_secs(timeout))
+        .build()?
+        .get(&source.url);
+    if !source
+        .header
+        .keys()
+        .any(|v| v.eq_ignore_ascii_case(USER_AGENT.as_str()))
+    {
+        req = req.header(USER_AGENT, DEFAULT_USER_AGENT);
+    }
+    for (k, values) in &source.header {
+        for v in values {
+            req = req.header(k, v);
+        }
+    }
+
+    // Send request, URLs are stripped from errors since they may carry tokens
+    let body = req
+        .send()
+        .await
+        .and_then(Response::error_for_status)
+        .map_err(reqwest::Error::without_url)?
+        .bytes()
+        .await
+        .map_err(reqwest::Error::without_url)?;
+    if body.is_empty() {
+        bail!("server responded with an empty body");
+    }
+
+    // Save file
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    file::write_atomic(path, body)
+}
+
+/// Strip the query of a URL, which often carries tokens
+fn get_display_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut v) => {
+            v.set_query(None);
+            v.to_string()
+        }
+        Err(_) => url.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+rule-providers:
+  ads:
+    type: http
+    behavior: domain
+    format: text
+    url: https://example.com/ads.txt?token=abc
+    interval: 86400
+    header:
+      User-Agent: [mihomo]
+  local:
+    type: file
+    behavior: domain
+    path: ./rules/local.yaml
+proxy-providers:
+  sub:
+    type: http
+    url: https://example.com/sub
+    proxy: DIRECT
+    health-check: {enable: true, url: https://example.com/204, interval: 300}
+  inline:
+    type: inline
+    payload: []
+"#;
+
+    #[test]
+    fn rewrite_http_providers() {
+        let mut value = serde_yaml::from_str::<Value>(CONFIG).unwrap();
+        let dir = Path::new("/mihomo/mihomosh_providers");
+        for (section, _) in PROVIDER_SECTIONS {
+            for (_, provider) in value[section].as_mapping_mut().unwrap() {
+                if let Some((_, file_name)) = get_source(provider) {
+                    rewrite_provider(provider, &dir.join(file_name));
+                }
+            }
+        }
+
+        let ads = &value["rule-providers"]["ads"];
+        let path = format!(
+            "/mihomo/mihomosh_providers/{}.txt",
+            &utils::hash("https://example.com/ads.txt?token=abc")[..16]
+        );
+        assert_eq!(ads["type"], Value::from("file"));
+        assert_eq!(ads["path"], Value::from(path));
+        assert_eq!(ads["behavior"], Value::from("domain"));
+        assert_eq!(ads["format"], Value::from("text"));
+        assert!(ads.get("url").is_none() && ads.get("interval").is_none());
+        assert!(ads.get("header").is_none());
+
+        let sub = &value["proxy-providers"]["sub"];
+        assert!(sub["path"].as_str().unwrap().ends_with(".yaml"));
+        assert!(sub.get("proxy").is_none());
+        assert_eq!(sub["health-check"]["interval"], Value::from(300));
+
+        assert_eq!(
+            value["rule-providers"]["local"]["path"],
+            Value::from("./rules/local.yaml")
+        );
+        assert!(value["proxy-providers"]["inline"].get("path").is_none());
+    }
+
+    #[test]
+    fn provider_sources() {
+        let value = serde_yaml::from_str::<Value>(CONFIG).unwrap();
+        let (source, _) = get_source(&value["rule-providers"]["ads"]).unwrap();
+        assert_eq!(source.url, "https://example.com/ads.txt?token=abc");
+        assert_eq!(source.header["User-Agent"], ["mihomo"]);
+        assert!(get_source(&value["rule-providers"]["local"]).is_none());
+    }
+
+    #[test]
+    fn prune_unused_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let mut index = BTreeMap::new();
+        for file_name in ["a.yaml", "b.txt", "c.mrs"] {
+            fs::write(dir.join(file_name), "payload: []").unwrap();
+            let source = Source {
+                url: format!("https://example.com/{}", file_name),
+                header: BTreeMap::new(),
+            };
+            index.insert(file_name.to_owned(), source);
+        }
+
+        let config = format!(
+            "rule-providers:\n  b: {{type: file, path: {}}}\n",
+            dir.join("b.txt").display()
+        );
+        let used = get_file_providers(&config)
+            .unwrap()
+            .into_iter()
+            .map(|(kind, name, path)| {
+                assert_eq!((kind, name.as_str()), ("rules", "b"));
+                path
+            })
+            .collect();
+        prune_index(dir, &mut index, &used).unwrap();
+        assert_eq!(index.keys().collect::<Vec<_>>(), ["b.txt"]);
+        assert!(dir.join("b.txt").is_file());
+        assert!(!dir.join("a.yaml").exists() && !dir.join("c.mrs").exists());
+    }
+}
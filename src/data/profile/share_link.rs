@@ -0,0 +1,793 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, bail, Result};
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use serde_yaml::{Mapping, Value};
+use url::Url;
+
+const BASE64_CONFIG: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+const BASE64_STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, BASE64_CONFIG);
+const BASE64_URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, BASE64_CONFIG);
+
+/// Default selector group name of converted configs
+const DEFAULT_GROUP: &str = "PROXY";
+
+/// Result of converting a subscription body
+pub struct Converted {
+    pub contents: String,
+
+    /// Number of invalid or unsupported share links skipped
+    pub skipped: usize,
+}
+
+/// Convert subscription body into a Mihomo config
+///
+/// Bodies which are already YAML mappings are returned unchanged, share link
+/// lists (either plain or base64 encoded) are converted into a config with
+/// `proxies`, a default selector group and a `MATCH` rule.
+pub fn convert<S: AsRef<str>>(body: S) -> Result<Converted> {
+    let body = body.as_ref();
+
+    // Check if it is already a config
+    if let Ok(Value::Mapping(_)) = serde_yaml::from_str::<Value>(body) {
+        return Ok(Converted {
+            contents: body.to_owned(),
+            skipped: 0,
+        });
+    }
+
+    // Get share links
+    let links = if body.contains("://") {
+        body.to_owned()
+    } else {
+        decode_base64(body).ok_or(anyhow!(
+            "subscription is neither a Mihomo config nor share links"
+        ))?
+    };
+
+    // Parse share links
+    let mut names = HashSet::new();
+    let mut proxies = Vec::new();
+    let mut skipped = 0;
+    for link in links.lines().map(str::trim).filter(|v| !v.is_empty()) {
+        let mut proxy = match parse_link(link) {
+            Ok(Some(v)) => v,
+            Ok(None) | Err(_) => {
+                skipped += 1;
+                continue;
+            }
+        };
+
+        // Deduplicate name, fallback to the server address
+        let name = match proxy.get("name").and_then(Value::as_str) {
+            Some(v) if !v.trim().is_empty() => v.to_owned(),
+            _ => get_address(&proxy),
+        };
+        let mut unique = name.clone();
+        let mut idx = 1;
+        while names.contains(&unique) {
+            idx += 1;
+            unique = format!("{} {}", name, idx);
+        }
+        proxy.insert("name".into(), unique.clone().into());
+        names.insert(unique);
+
+        proxies.push(Value::Mapping(proxy));
+    }
+    if proxies.is_empty() {
+        bail!("no supported share links found in subscription");
+    }
+
+    // Build config
+    let mut group = Mapping::new();
+    group.insert("name".into(), DEFAULT_GROUP.into());
+    group.insert("type".into(), "select".into());
+    let mut members = proxies
+        .iter()
+        .map(|v| v["name"].clone())
+        .collect::<Vec<_>>();
+    members.push("DIRECT".into());
+    group.insert("proxies".into(), Value::Sequence(members));
+
+    let mut config = Mapping::new();
+    config.insert("proxies".into(), Value::Sequence(proxies));
+    config.insert(
+        "proxy-groups".into(),
+        Value::Sequence(vec![Value::Mapping(group)]),
+    );
+    config.insert(
+        "rules".into(),
+        Value::Sequence(vec![format!("MATCH,{}", DEFAULT_GROUP).into()]),
+    );
+
+    Ok(Converted {
+        contents: serde_yaml::to_string(&config)?,
+        skipped,
+    })
+}
+
+fn decode_base64<S: AsRef<str>>(data: S) -> Option<String> {
+    let data = data
+        .as_ref()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    let buf = BASE64_STANDARD
+        .decode(&data)
+        .or_else(|_| BASE64_URL_SAFE.decode(&data))
+        .ok()?;
+
+    String::from_utf8(buf).ok()
+}
+
+/// Get `server:port` of a proxy, with IPv6 servers bracketed
+fn get_address(proxy: &Mapping) -> String {
+    let server = proxy
+        .get("server")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let port = proxy
+        .get("port")
+        .and_then(Value::as_u64)
+        .unwrap_or_default();
+    if server.contains(':') {
+        format!("[{}]:{}", server, port)
+    } else {
+        format!("{}:{}", server, port)
+    }
+}
+
+fn parse_link(link: &str) -> Result<Option<Mapping>> {
+    let (scheme, _) = link
+        .split_once("://")
+        .ok_or(anyhow!("invalid share link"))?;
+    match scheme.to_lowercase().as_str() {
+        "ss" => parse_ss(link).map(Some),
+        "ssr" => parse_ssr(link).map(Some),
+        "vmess" => parse_vmess(link).map(Some),
+        "vless" => parse_vless(link).map(Some),
+        "trojan" => parse_trojan(link).map(Some),
+        "hysteria2" | "hy2" => parse_hysteria2(link).map(Some),
+        "tuic" => parse_tuic(link).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn parse_ss(link: &str) -> Result<Mapping> {
+    let rest = &link["ss://".len()..];
+    let (rest, name) = split_name(rest);
+
+    // Legacy format: ss://base64(method:password@host:port)
+    let url = if rest.contains('@') {
+        Url::parse(&format!("ss://{}", rest))?
+    } else {
+        let (encoded, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let encoded = encoded.trim_end_matches('/');
+        let decoded = decode_base64(encoded).ok_or(anyhow!("invalid ss link"))?;
+        let (userinfo, server) = decoded.rsplit_once('@').ok_or(anyhow!("invalid ss link"))?;
+        Url::parse(&format!(
+            "ss://{}@{}?{}",
+            BASE64_URL_SAFE.encode(userinfo),
+            server,
+            query
+        ))?
+    };
+
+    // SIP002 userinfo is either base64 or percent-encoded
+    let userinfo = if url.password().is_some() {
+        format!(
+            "{}:{}",
+            percent_decode(url.username()),
+            percent_decode(url.password().unwrap())
+        )
+    } else {
+        let username = percent_decode(url.username());
+        decode_base64(&username).unwrap_or(username)
+    };
+    let (cipher, password) = userinfo
+        .split_once(':')
+        .ok_or(anyhow!("invalid ss userinfo"))?;
+
+    let mut proxy = new_proxy(&name, "ss", &url)?;
+    proxy.insert("cipher".into(), cipher.into());
+    proxy.insert("password".into(), password.into());
+    proxy.insert("udp".into(), true.into());
+
+    // Plugin
+    let query = get_query(&url);
+    if let Some(plugin) = query.get("plugin") {
+        let mut segs = plugin.split(';');
+        let kind = segs.next().unwrap_or("");
+        let opts = segs
+            .filter_map(|v| v.split_once('=').or(Some((v, ""))))
+            .collect::<HashMap<_, _>>();
+
+        let mut plugin_opts = Mapping::new();
+        if kind.contains("obfs") {
+            proxy.insert("plugin".into(), "obfs".into());
+            if let Some(mode) = opts.get("obfs") {
+                plugin_opts.insert("mode".into(), (*mode).into());
+            }
+            if let Some(host) = opts.get("obfs-host") {
+                plugin_opts.insert("host".into(), (*host).into());
+            }
+        } else if kind.contains("v2ray") {
+            proxy.insert("plugin".into(), "v2ray-plugin".into());
+            plugin_opts.insert(
+                "mode".into(),
+                opts.get("mode").copied().unwrap_or("websocket").into(),
+            );
+            if let Some(host) = opts.get("host") {
+                plugin_opts.insert("host".into(), (*host).into());
+            }
+            if let Some(path) = opts.get("path") {
+                plugin_opts.insert("path".into(), (*path).into());
+            }
+            if opts.contains_key("tls") {
+                plugin_opts.insert("tls".into(), true.into());
+            }
+        } else {
+            bail!("unsupported ss plugin `{}`", kind);
+        }
+        proxy.insert("plugin-opts".into(), Value::Mapping(plugin_opts));
+    }
+
+    Ok(proxy)
+}
+
+fn parse_ssr(link: &str) -> Result<Mapping> {
+    let decoded = decode_base64(&link["ssr://".len()..]).ok_or(anyhow!("invalid ssr link"))?;
+    let (main, params) = decoded.split_once("/?").unwrap_or((&decoded, ""));
+
+    // Parse main part from right, host may be IPv6
+    let mut segs = main.rsplitn(6, ':');
+    let password = segs.next().ok_or(anyhow!("invalid ssr link"))?;
+    let obfs = segs.next().ok_or(anyhow!("invalid ssr link"))?;
+    let cipher = segs.next().ok_or(anyhow!("invalid ssr link"))?;
+    let protocol = segs.next().ok_or(anyhow!("invalid ssr link"))?;
+    let port = segs.next().ok_or(anyhow!("invalid ssr link"))?;
+    let server = segs.next().ok_or(anyhow!("invalid ssr link"))?;
+
+    // Parse params
+    let params = params
+        .split('&')
+        .filter_map(|v| v.split_once('='))
+        .map(|(k, v)| (k, decode_base64(v).unwrap_or_default()))
+        .collect::<HashMap<_, _>>();
+
+    let mut proxy = Mapping::new();
+    proxy.insert(
+        "name".into(),
+        params.get("remarks").cloned().unwrap_or_default().into(),
+    );
+    proxy.insert("type".into(), "ssr".into());
+    proxy.insert("server".into(), server.into());
+    proxy.insert("port".into(), port.parse::<u16>()?.into());
+    proxy.insert("cipher".into(), cipher.into());
+    proxy.insert(
+        "password".into(),
+        decode_base64(password)
+            .ok_or(anyhow!("invalid ssr password"))?
+            .into(),
+    );
+    proxy.insert("obfs".into(), obfs.into());
+    proxy.insert("protocol".into(), protocol.into());
+    if let Some(v) = params.get("obfsparam").filter(|v| !v.is_empty()) {
+        proxy.insert("obfs-param".into(), v.as_str().into());
+    }
+    if let Some(v) = params.get("protoparam").filter(|v| !v.is_empty()) {
+        proxy.insert("protocol-param".into(), v.as_str().into());
+    }
+    proxy.insert("udp".into(), true.into());
+
+    Ok(proxy)
+}
+
+fn parse_vmess(link: &str) -> Result<Mapping> {
+    let decoded = decode_base64(&link["vmess://".len()..]).ok_or(anyhow!("invalid vmess link"))?;
+    let json = serde_json::from_str::<serde_json::Value>(&decoded)?;
+    let get = |k: &str| match json.get(k) {
+        Some(serde_json::Value::String(v)) => v.trim().to_owned(),
+        Some(serde_json::Value::Number(v)) => v.to_string(),
+        _ => String::new(),
+    };
+
+    let mut proxy = Mapping::new();
+    proxy.insert("name".into(), get("ps").into());
+    proxy.insert("type".into(), "vmess".into());
+    proxy.insert("server".into(), get("add").into());
+    proxy.insert("port".into(), get("port").parse::<u16>()?.into());
+    proxy.insert("uuid".into(), get("id").into());
+    proxy.insert(
+        "alterId".into(),
+        get("aid").parse::<u32>().unwrap_or(0).into(),
+    );
+    let cipher = get("scy");
+    proxy.insert(
+        "cipher".into(),
+        if cipher.is_empty() {
+            "auto".into()
+        } else {
+            cipher
+        }
+        .into(),
+    );
+    proxy.insert("udp".into(), true.into());
+
+    // TLS
+    let security = get("tls");
+    if security == "tls" {
+        proxy.insert("tls".into(), true.into());
+        let sni = get("sni");
+        if !sni.is_empty() {
+            proxy.insert("servername".into(), sni.into());
+        }
+        let alpn = get("alpn");
+        if !alpn.is_empty() {
+            proxy.insert("alpn".into(), split_list(&alpn));
+        }
+        let fp = get("fp");
+        if !fp.is_empty() {
+            proxy.insert("client-fingerprint".into(), fp.into());
+        }
+    }
+
+    // Transport
+    let net = get("net");
+    let net = if net == "http" || get("type") == "http" {
+        "http".to_owned()
+    } else {
+        net
+    };
+    apply_transport(&mut proxy, &net, &get("host"), &get("path"), &get("path"));
+
+    Ok(proxy)
+}
+
+fn parse_vless(link: &str) -> Result<Mapping> {
+    let (rest, name) = split_name(link);
+    let url = Url::parse(rest)?;
+    let query = get_query(&url);
+
+    let mut proxy = new_proxy(&name, "vless", &url)?;
+    proxy.insert("uuid".into(), percent_decode(url.username()).into());
+    proxy.insert("udp".into(), true.into());
+    if let Some(flow) = query.get("flow").filter(|v| !v.is_empty()) {
+        proxy.insert("flow".into(), flow.as_str().into());
+    }
+    apply_tls(&mut proxy, &query, "servername");
+    apply_query_transport(&mut proxy, &query);
+
+    Ok(proxy)
+}
+
+fn parse_trojan(link: &str) -> Result<Mapping> {
+    let (rest, name) = split_name(link);
+    let url = Url::parse(rest)?;
+    let query = get_query(&url);
+
+    let mut proxy = new_proxy(&name, "trojan", &url)?;
+    proxy.insert("password".into(), percent_decode(url.username()).into());
+    proxy.insert("udp".into(), true.into());
+    apply_tls(&mut proxy, &query, "sni");
+    proxy.remove("tls");
+    apply_query_transport(&mut proxy, &query);
+
+    Ok(proxy)
+}
+
+fn parse_hysteria2(link: &str) -> Result<Mapping> {
+    let (rest, name) = split_name(link);
+    let url = Url::parse(rest)?;
+    let query = get_query(&url);
+
+    let mut proxy = new_proxy(&name, "hysteria2", &url)?;
+    let password = match url.password() {
+        Some(v) => format!("{}:{}", percent_decode(url.username()), percent_decode(v)),
+        None => percent_decode(url.username()),
+    };
+    proxy.insert("password".into(), password.into());
+    if let Some(v) = query.get("sni") {
+        proxy.insert("sni".into(), v.as_str().into());
+    }
+    if let Some(v) = query.get("obfs").filter(|v| v.as_str() != "none") {
+        proxy.insert("obfs".into(), v.as_str().into());
+    }
+    if let Some(v) = query.get("obfs-password") {
+        proxy.insert("obfs-password".into(), v.as_str().into());
+    }
+    if let Some(v) = query.get("mport") {
+        proxy.insert("ports".into(), v.as_str().into());
+    }
+    if is_truthy(query.get("insecure")) {
+        proxy.insert("skip-cert-verify".into(), true.into());
+    }
+
+    Ok(proxy)
+}
+
+fn parse_tuic(link: &str) -> Result<Mapping> {
+    let (rest, name) = split_name(link);
+    let url = Url::parse(rest)?;
+    let query = get_query(&url);
+
+    let mut proxy = new_proxy(&name, "tuic", &url)?;
+    proxy.insert("uuid".into(), percent_decode(url.username()).into());
+    proxy.insert(
+        "password".into(),
+        percent_decode(url.password().unwrap_or("")).into(),
+    );
+    if let Some(v) = query.get("sni") {
+        proxy.insert("sni".into(), v.as_str().into());
+    }
+    if let Some(v) = query.get("alpn") {
+        proxy.insert("alpn".into(), split_list(v));
+    }
+    if let Some(v) = query.get("congestion_control") {
+        proxy.insert("congestion-controller".into(), v.as_str().into());
+    }
+    if let Some(v) = query.get("udp_relay_mode") {
+        proxy.insert("udp-relay-mode".into(), v.as_str().into());
+    }
+    if is_truthy(query.get("disable_sni")) {
+        proxy.insert("disable-sni".into(), true.into());
+    }
+    if is_truthy(query.get("allow_insecure")) || is_truthy(query.get("insecure")) {
+        proxy.insert("skip-cert-verify".into(), true.into());
+    }
+
+    Ok(proxy)
+}
+
+fn new_proxy(name: &str, r#type: &str, url: &Url) -> Result<Mapping> {
+    let server = url
+        .host_str()
+        .ok_or(anyhow!("share link has no server"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = url.port().ok_or(anyhow!("share link has no port"))?;
+
+    let mut proxy = Mapping::new();
+    proxy.insert("name".into(), name.into());
+    proxy.insert("type".into(), r#type.into());
+    proxy.insert("server".into(), server.into());
+    proxy.insert("port".into(), port.into());
+
+    Ok(proxy)
+}
+
+fn apply_tls(proxy: &mut Mapping, query: &HashMap<String, String>, sni_key: &str) {
+    let security = query.get("security").map_or("", String::as_str);
+    if security == "tls" || security == "reality" {
+        proxy.insert("tls".into(), true.into());
+    }
+    if let Some(v) = query.get("sni").or(query.get("peer")) {
+        proxy.insert(sni_key.into(), v.as_str().into());
+    }
+    if let Some(v) = query.get("alpn") {
+        proxy.insert("alpn".into(), split_list(v));
+    }
+    if let Some(v) = query.get("fp") {
+        proxy.insert("client-fingerprint".into(), v.as_str().into());
+    }
+    if is_truthy(query.get("allowInsecure")) || is_truthy(query.get("insecure")) {
+        proxy.insert("skip-cert-verify".into(), true.into());
+    }
+    if security == "reality" {
+        let mut opts = Mapping::new();
+        if let Some(v) = query.get("pbk") {
+            opts.insert("public-key".into(), v.as_str().into());
+        }
+        if let Some(v) = query.get("sid") {
+            opts.insert("short-id".into(), v.as_str().into());
+        }
+        proxy.insert("reality-opts".into(), Value::Mapping(opts));
+    }
+}
+
+fn apply_query_transport(proxy: &mut Mapping, query: &HashMap<String, String>) {
+    let net = query.get("type").map_or("tcp", String::as_str);
+    let net = if net == "tcp" && query.get("headerType").map(String::as_str) == Some("http") {
+        "http"
+    } else {
+        net
+    };
+    let host = query.get("host").map_or("", String::as_str);
+    let path = query.get("path").map_or("", String::as_str);
+    let service_name = query.get("serviceName").map_or("", String::as_str);
+    apply_transport(proxy, net, host, path, service_name);
+}
+
+fn apply_transport(proxy: &mut Mapping, net: &str, host: &str, path: &str, service_name: &str) {
+    let mut opts = Mapping::new();
+    let key = match net {
+        "ws" | "httpupgrade" => {
+            if !path.is_empty() {
+                opts.insert("path".into(), path.into());
+            }
+            if !host.is_empty() {
+                let mut headers = Mapping::new();
+                headers.insert("Host".into(), host.into());
+                opts.insert("headers".into(), Value::Mapping(headers));
+            }
+            if net == "httpupgrade" {
+                opts.insert("v2ray-http-upgrade".into(), true.into());
+            }
+            "ws-opts"
+        }
+        "grpc" => {
+            if !service_name.is_empty() {
+                opts.insert("grpc-service-name".into(), service_name.into());
+            }
+            "grpc-opts"
+        }
+        "h2" => {
+            if !host.is_empty() {
+                opts.insert("host".into(), split_list(host));
+            }
+            if !path.is_empty() {
+                opts.insert("path".into(), path.into());
+            }
+            "h2-opts"
+        }
+        "http" => {
+            if !path.is_empty() {
+                opts.insert("path".into(), split_list(path));
+            }
+            if !host.is_empty() {
+                let mut headers = Mapping::new();
+                headers.insert("Host".into(), split_list(host));
+                opts.insert("headers".into(), Value::Mapping(headers));
+            }
+            "http-opts"
+        }
+        _ => return,
+    };
+
+    let network = if net == "httpupgrade" { "ws" } else { net };
+    proxy.insert("network".into(), network.into());
+    if !opts.is_empty() {
+        proxy.insert(key.into(), Value::Mapping(opts));
+    }
+}
+
+fn split_name(link: &str) -> (&str, String) {
+    match link.split_once('#') {
+        Some((rest, name)) => (rest, percent_decode(name)),
+        None => (link, String::new()),
+    }
+}
+
+fn get_query(url: &Url) -> HashMap<String, String> {
+    url.query_pairs().into_owned().collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    urlencoding::decode(s).map_or(s.to_owned(), |v| v.into_owned())
+}
+
+fn split_list(s: &str) -> Value {
+    Value::Sequence(
+        s.split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(Into::into)
+            .collect(),
+    )
+}
+
+fn is_truthy(v: Option<&String>) -> bool {
+    matches!(v.map(String::as_str), Some("1" | "true"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(link: &str) -> Mapping {
+        parse_link(link).unwrap().unwrap()
+    }
+
+    fn get<'a>(proxy: &'a Mapping, key: &str) -> &'a Value {
+        proxy.get(key).unwrap_or(&Value::Null)
+    }
+
+    #[test]
+    fn ss_sip002_base64() {
+        let proxy = parse("ss://YWVzLTI1Ni1nY206cGFzcw@1.2.3.4:8388#Node%20A");
+        assert_eq!(get(&proxy, "name"), "Node A");
+        assert_eq!(get(&proxy, "type"), "ss");
+        assert_eq!(get(&proxy, "server"), "1.2.3.4");
+        assert_eq!(get(&proxy, "port"), 8388);
+        assert_eq!(get(&proxy, "cipher"), "aes-256-gcm");
+        assert_eq!(get(&proxy, "password"), "pass");
+    }
+
+    #[test]
+    fn ss_sip002_plain() {
+        let proxy = parse("ss://2022-blake3-aes-128-gcm:abc%2Bdef@[::1]:443#B");
+        assert_eq!(get(&proxy, "server"), "::1");
+        assert_eq!(get(&proxy, "cipher"), "2022-blake3-aes-128-gcm");
+        assert_eq!(get(&proxy, "password"), "abc+def");
+    }
+
+    #[test]
+    fn ss_legacy() {
+        let encoded = BASE64_STANDARD.encode("aes-128-gcm:p@ss@example.com:1234");
+        let proxy = parse(&format!("ss://{}#Legacy", encoded));
+        assert_eq!(get(&proxy, "name"), "Legacy");
+        assert_eq!(get(&proxy, "server"), "example.com");
+        assert_eq!(get(&proxy, "port"), 1234);
+        assert_eq!(get(&proxy, "cipher"), "aes-128-gcm");
+        assert_eq!(get(&proxy, "password"), "p@ss");
+    }
+
+    #[test]
+    fn ss_plugin() {
+        let proxy = parse(
+            "ss://YWVzLTI1Ni1nY206cGFzcw@h.com:1?plugin=obfs-local%3Bobfs%3Dhttp%3Bobfs-host%3Dx.com",
+        );
+        assert_eq!(get(&proxy, "plugin"), "obfs");
+        let opts = get(&proxy, "plugin-opts");
+        assert_eq!(opts["mode"], "http");
+        assert_eq!(opts["host"], "x.com");
+
+        assert!(parse_link("ss://YWVzLTI1Ni1nY206cGFzcw@h.com:1?plugin=unknown").is_err());
+    }
+
+    #[test]
+    fn ssr() {
+        let link = format!(
+            "1.2.3.4:8388:auth_aes128_md5:aes-256-cfb:tls1.2_ticket_auth:{}/?remarks={}&obfsparam={}",
+            BASE64_STANDARD.encode("pw"),
+            BASE64_URL_SAFE.encode("SSR Node"),
+            BASE64_URL_SAFE.encode("x.com"),
+        );
+        let proxy = parse(&format!("ssr://{}", BASE64_URL_SAFE.encode(link)));
+        assert_eq!(get(&proxy, "name"), "SSR Node");
+        assert_eq!(get(&proxy, "type"), "ssr");
+        assert_eq!(get(&proxy, "server"), "1.2.3.4");
+        assert_eq!(get(&proxy, "port"), 8388);
+        assert_eq!(get(&proxy, "protocol"), "auth_aes128_md5");
+        assert_eq!(get(&proxy, "cipher"), "aes-256-cfb");
+        assert_eq!(get(&proxy, "obfs"), "tls1.2_ticket_auth");
+        assert_eq!(get(&proxy, "password"), "pw");
+        assert_eq!(get(&proxy, "obfs-param"), "x.com");
+    }
+
+    #[test]
+    fn vmess() {
+        let json = r#"{"v":"2","ps":"VM","add":"v.com","port":"443","id":"u-u-i-d","aid":"0","net":"ws","host":"h.com","path":"/ws","tls":"tls","sni":"s.com"}"#;
+        let proxy = parse(&format!("vmess://{}", BASE64_STANDARD.encode(json)));
+        assert_eq!(get(&proxy, "name"), "VM");
+        assert_eq!(get(&proxy, "type"), "vmess");
+        assert_eq!(get(&proxy, "server"), "v.com");
+        assert_eq!(get(&proxy, "port"), 443);
+        assert_eq!(get(&proxy, "uuid"), "u-u-i-d");
+        assert_eq!(get(&proxy, "alterId"), 0);
+        assert_eq!(get(&proxy, "cipher"), "auto");
+        assert_eq!(get(&proxy, "tls"), &Value::Bool(true));
+        assert_eq!(get(&proxy, "servername"), "s.com");
+        assert_eq!(get(&proxy, "network"), "ws");
+        let opts = get(&proxy, "ws-opts");
+        assert_eq!(opts["path"], "/ws");
+        assert_eq!(opts["headers"]["Host"], "h.com");
+    }
+
+    #[test]
+    fn vless_reality() {
+        let proxy = parse(
+            "vless://u-u-i-d@1.2.3.4:443?security=reality&pbk=PUB&sid=ab&sni=www.example.com&fp=chrome&flow=xtls-rprx-vision&type=tcp#R",
+        );
+        assert_eq!(get(&proxy, "name"), "R");
+        assert_eq!(get(&proxy, "type"), "vless");
+        assert_eq!(get(&proxy, "uuid"), "u-u-i-d");
+        assert_eq!(get(&proxy, "flow"), "xtls-rprx-vision");
+        assert_eq!(get(&proxy, "tls"), &Value::Bool(true));
+        assert_eq!(get(&proxy, "servername"), "www.example.com");
+        assert_eq!(get(&proxy, "client-fingerprint"), "chrome");
+        let opts = get(&proxy, "reality-opts");
+        assert_eq!(opts["public-key"], "PUB");
+        assert_eq!(opts["short-id"], "ab");
+        assert!(proxy.get("network").is_none());
+    }
+
+    #[test]
+    fn trojan() {
+        let proxy =
+            parse("trojan://pass@t.com:443?sni=s.com&type=grpc&serviceName=svc&allowInsecure=1#T");
+        assert_eq!(get(&proxy, "type"), "trojan");
+        assert_eq!(get(&proxy, "password"), "pass");
+        assert_eq!(get(&proxy, "sni"), "s.com");
+        assert_eq!(get(&proxy, "skip-cert-verify"), &Value::Bool(true));
+        assert!(proxy.get("tls").is_none());
+        assert_eq!(get(&proxy, "network"), "grpc");
+        assert_eq!(get(&proxy, "grpc-opts")["grpc-service-name"], "svc");
+    }
+
+    #[test]
+    fn hysteria2() {
+        let proxy = parse(
+            "hysteria2://pw@h.com:443?sni=s.com&obfs=salamander&obfs-password=op&insecure=1#H",
+        );
+        assert_eq!(get(&proxy, "type"), "hysteria2");
+        assert_eq!(get(&proxy, "password"), "pw");
+        assert_eq!(get(&proxy, "sni"), "s.com");
+        assert_eq!(get(&proxy, "obfs"), "salamander");
+        assert_eq!(get(&proxy, "obfs-password"), "op");
+        assert_eq!(get(&proxy, "skip-cert-verify"), &Value::Bool(true));
+
+        let proxy = parse("hy2://user:pw@h.com:443?obfs=none");
+        assert_eq!(get(&proxy, "password"), "user:pw");
+        assert!(proxy.get("obfs").is_none());
+    }
+
+    #[test]
+    fn tuic() {
+        let proxy =
+            parse("tuic://u-u-i-d:pw@t.com:443?alpn=h3,h2&congestion_control=bbr&disable_sni=1#TU");
+        assert_eq!(get(&proxy, "type"), "tuic");
+        assert_eq!(get(&proxy, "uuid"), "u-u-i-d");
+        assert_eq!(get(&proxy, "password"), "pw");
+        assert_eq!(get(&proxy, "alpn"), &Value::from(vec!["h3", "h2"]));
+        assert_eq!(get(&proxy, "congestion-controller"), "bbr");
+        assert_eq!(get(&proxy, "disable-sni"), &Value::Bool(true));
+    }
+
+    #[test]
+    fn invalid_links() {
+        assert!(parse_link("socks://h.com:1").unwrap().is_none());
+        assert!(parse_link("vless://u@h.com").is_err());
+        assert!(parse_link("vmess://not-base64").is_err());
+        assert!(parse_link("no scheme").is_err());
+    }
+
+    #[test]
+    fn convert_dedupes_names() {
+        let body = [
+            "trojan://a@h.com:1#A",
+            "trojan://b@h.com:2#A",
+            "trojan://c@h.com:3#A",
+            "trojan://d@h.com:4",
+            "trojan://e@h.com:4#",
+            "trojan://f@[::1]:5",
+        ]
+        .join("\n");
+        let converted = convert(BASE64_STANDARD.encode(body)).unwrap();
+        assert_eq!(converted.skipped, 0);
+
+        let value = serde_yaml::from_str::<Value>(&converted.contents).unwrap();
+        let names = value["proxies"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|v| v["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["A", "A 2", "A 3", "h.com:4", "h.com:4 2", "[::1]:5"]
+        );
+        assert_eq!(value["proxy-groups"][0]["proxies"][6], "DIRECT");
+        assert_eq!(value["rules"][0], "MATCH,PROXY");
+    }
+
+    #[test]
+    fn convert_counts_skipped() {
+        let body = "trojan://a@h.com:1#A\nsocks://h.com:1\nvless://broken\n";
+        assert_eq!(convert(body).unwrap().skipped, 2);
+        assert!(convert("socks://h.com:1").is_err());
+    }
+
+    #[test]
+    fn convert_keeps_configs() {
+        let body = "proxies: []\n";
+        let converted = convert(body).unwrap();
+        assert_eq!(converted.contents, body);
+        assert_eq!(converted.skipped, 0);
+    }
+}
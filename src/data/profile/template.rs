@@ -0,0 +1,229 @@
+use anyhow::{bail, Context, Result};
+use serde_yaml::{Mapping, Value};
+
+use super::{Config, ConfigType, Metas, URL_TEST_INTERVAL, URL_TEST_URL};
+
+/// Item of `proxy-groups` in the template, replaced by the generated groups
+const GROUPS_MARKER: &str = "$groups";
+
+/// Member of a template group, replaced by all spliced node names
+const NODES_MARKER: &str = "$nodes";
+
+/// Member of a template group, replaced by the generated url-test group name
+const AUTO_MARKER: &str = "$auto";
+
+/// Splice the nodes of a profile into its template profile
+pub fn splice<S: AsRef<str>>(conf: &Config, data: S) -> Result<String> {
+    let Some(uuid_or_name) = &conf.template else {
+        return Ok(data.as_ref().to_owned());
+    };
+
+    // Load template profile
+    let template = {
+        let metas = Metas::get_instance().lock().unwrap();
+        let meta = metas
+            .try_get_meta(uuid_or_name)
+            .with_context(|| format!("try to get profile metadata by `{}`", uuid_or_name))?;
+        meta.try_get_conf()
+            .with_context(|| format!("try to get profile config by UUID `{}`", meta.uuid))?
+    };
+    if template.uuid == conf.uuid
+        || template.template.is_some()
+        || matches!(template.r#type, ConfigType::Remote | ConfigType::Composite)
+    {
+        bail!(
+            "profile `{}` cannot be a template, it must be a plain local or file profile",
+            template.name
+        );
+    }
+
+    build(
+        &template.name,
+        &template.read_data()?,
+        &conf.name,
+        data.as_ref(),
+    )
+}
+
+/// Build data from template profile name and data, and name and data of the nodes profile
+fn build(template: &str, contents: &str, name: &str, data: &str) -> Result<String> {
+    let mut value = serde_yaml::from_str::<Value>(contents)
+        .with_context(|| format!("try to parse data of profile `{}`", template))?;
+    if !value.is_mapping() {
+        bail!("data of template profile `{}` must be an object", template);
+    }
+
+    // Collect nodes, replacing template ones with the same name
+    let nodes = serde_yaml::from_str::<Value>(data)?
+        .get("proxies")
+        .and_then(Value::as_sequence)
+        .cloned()
+        .unwrap_or_default();
+    let names = nodes
+        .iter()
+        .filter_map(|v| v.get("name").and_then(Value::as_str))
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    let proxies = value
+        .as_mapping_mut()
+        .unwrap()
+        .entry("proxies".into())
+        .or_insert(Value::Sequence(Vec::new()));
+    if let Some(proxies) = proxies.as_sequence_mut() {
+        proxies.retain(|v| {
+            v.get("name")
+                .and_then(Value::as_str)
+                .is_none_or(|v| !names.iter().any(|n| n == v))
+        });
+        proxies.extend(nodes);
+    }
+
+    // Build generated groups
+    let auto = format!("{}-auto", name);
+    let taken = value
+        .get("proxy-groups")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .chain(value["proxies"].as_sequence().into_iter().flatten())
+        .any(|v| v.get("name").and_then(Value::as_str) == Some(&auto));
+    if taken {
+        bail!(
+            "generated group `{}` collides with a group or node spliced into template profile `{}`",
+            auto,
+            template
+        );
+    }
+    let mut group = Mapping::new();
+    group.insert("name".into(), auto.clone().into());
+    group.insert("type".into(), "url-test".into());
+    group.insert(
+        "proxies".into(),
+        if names.is_empty() {
+            vec!["DIRECT".to_owned()]
+        } else {
+            names.clone()
+        }
+        .into(),
+    );
+    group.insert("url".into(), URL_TEST_URL.into());
+    group.insert("interval".into(), URL_TEST_INTERVAL.into());
+    let generated = vec![Value::Mapping(group)];
+
+    // Expand markers in template groups
+    let groups = value
+        .as_mapping_mut()
+        .unwrap()
+        .entry("proxy-groups".into())
+        .or_insert(Value::Sequence(Vec::new()));
+    if let Some(groups) = groups.as_sequence_mut() {
+        let mut expanded = Vec::new();
+        let mut placed = false;
+        for group in groups.drain(..) {
+            if group.as_str() == Some(GROUPS_MARKER) {
+                expanded.extend(generated.clone());
+                placed = true;
+                continue;
+            }
+            expanded.push(expand_members(group, &names, &auto));
+        }
+        if !placed {
+            expanded.extend(generated);
+        }
+        *groups = expanded;
+    }
+
+    Ok(serde_yaml::to_string(&value)?)
+}
+
+fn expand_members(mut group: Value, names: &[String], auto: &str) -> Value {
+    let has_providers = group.get("use").is_some();
+    let Some(members) = group.get_mut("proxies").and_then(Value::as_sequence_mut) else {
+        return group;
+    };
+
+    let mut expanded = Vec::new();
+    for member in members.drain(..) {
+        match member.as_str() {
+            Some(NODES_MARKER) => expanded.extend(names.iter().map(|v| v.as_str().into())),
+            Some(AUTO_MARKER) => expanded.push(auto.into()),
+            _ => expanded.push(member),
+        }
+    }
+    if expanded.is_empty() && !has_providers {
+        expanded.push("DIRECT".into());
+    }
+    *members = expanded;
+
+    group
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = r#"
+proxies:
+  - {name: home, type: socks5}
+  - {name: a, type: http}
+proxy-groups:
+  - {name: PROXY, type: select, proxies: [$auto, $nodes, home]}
+  - $groups
+  - {name: Empty, type: select, proxies: [$nodes]}
+  - {name: Remote, type: select, use: [provider], proxies: [$nodes]}
+rules:
+  - MATCH,PROXY
+"#;
+
+    const DATA: &str = "proxies:\n- {name: a, type: socks5}\n- {name: b, type: socks5}\n";
+
+    fn build_value(template: &str, data: &str) -> Value {
+        serde_yaml::from_str(&build("Tpl", template, "Nodes", data).unwrap()).unwrap()
+    }
+
+    fn names(value: &Value) -> Vec<&str> {
+        value
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().or(v["name"].as_str()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn splice_markers() {
+        let value = build_value(TEMPLATE, DATA);
+        assert_eq!(names(&value["proxies"]), ["home", "a", "b"]);
+        assert_eq!(value["proxies"][1]["type"], Value::from("socks5"));
+
+        let groups = &value["proxy-groups"];
+        assert_eq!(names(groups), ["PROXY", "Nodes-auto", "Empty", "Remote"]);
+        assert_eq!(
+            names(&groups[0]["proxies"]),
+            ["Nodes-auto", "a", "b", "home"]
+        );
+        assert_eq!(names(&groups[1]["proxies"]), ["a", "b"]);
+        assert_eq!(groups[1]["url"], Value::from(URL_TEST_URL));
+        assert_eq!(names(&value["rules"]), ["MATCH,PROXY"]);
+    }
+
+    #[test]
+    fn splice_without_nodes() {
+        let value = build_value(TEMPLATE, "rules: []");
+        let groups = &value["proxy-groups"];
+        assert_eq!(names(&groups[0]["proxies"]), ["Nodes-auto", "home"]);
+        assert_eq!(names(&groups[1]["proxies"]), ["DIRECT"]);
+        assert_eq!(names(&groups[2]["proxies"]), ["DIRECT"]);
+        assert!(groups[3]["proxies"].as_sequence().unwrap().is_empty());
+    }
+
+    #[test]
+    fn splice_generated_groups() {
+        let value = build_value("rules: [MATCH,DIRECT]", DATA);
+        assert_eq!(names(&value["proxy-groups"]), ["Nodes-auto"]);
+
+        let template = "proxy-groups:\n- {name: Nodes-auto, type: select}\n";
+        assert!(build("Tpl", template, "Nodes", DATA).is_err());
+        assert!(build("Tpl", "- a", "Nodes", DATA).is_err());
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use anyhow::Result;
+use serde_yaml::Value;
+
+/// Built-in proxies which are always available as group members and rule targets
+pub(super) const BUILTIN_PROXIES: [&str; 6] = [
+    "DIRECT",
+    "REJECT",
+    "REJECT-DROP",
+    "PASS",
+    "COMPATIBLE",
+    "GLOBAL",
+];
+
+/// Rule types whose payload contains commas in parentheses
+const LOGIC_RULES: [&str; 4] = ["AND", "OR", "NOT", "SUB-RULE"];
+
+/// Top-level port fields, with whether they listen on TCP and UDP
+const PORT_FIELDS: [(&str, bool, bool); 7] = [
+    ("port", true, false),
+    ("socks-port", true, true),
+    ("mixed-port", true, true),
+    ("redir-port", true, false),
+    ("tproxy-port", true, true),
+    ("external-controller", true, false),
+    ("external-controller-tls", true, false),
+];
+
+/// Listener types listening on UDP only
+const UDP_LISTENERS: [&str; 2] = ["tuic", "hysteria2"];
+
+/// Listener types listening on both TCP and UDP
+const DUAL_LISTENERS: [&str; 5] = ["socks", "mixed", "tproxy", "tunnel", "shadowsocks"];
+
+/// Problem found in a rendered Mihomo config
+pub struct Problem {
+    /// Path of the problematic field, e.g. `proxy-groups[0].proxies[1]`
+    pub path: String,
+    pub message: String,
+}
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "`{}`: {}", self.path, self.message)
+    }
+}
+
+/// Check a rendered Mihomo config for broken references and conflicts
+pub fn validate_rendered<S: AsRef<str>>(contents: S) -> Result<Vec<Problem>> {
+    let value = serde_yaml::from_str::<Value>(contents.as_ref())?;
+    let mut problems = Vec::new();
+    let mut problem = |path: String, message: String| problems.push(Problem { path, message });
+
+    if !value.is_mapping() {
+        problem("$".into(), "config must be an object".into());
+        return Ok(problems);
+    }
+
+    // Check proxy and group names
+    let proxies = get_seq(&value, "proxies");
+    let groups = get_seq(&value, "proxy-groups");
+    let mut names = HashSet::new();
+    for (key, list) in [("proxies", proxies), ("proxy-groups", groups)] {
+        for (idx, item) in list.iter().enumerate() {
+            match item.get("name").and_then(Value::as_str) {
+                Some(name) if !names.insert(name) => problem(
+                    format!("{}[{}].name", key, idx),
+                    format!("name `{}` is duplicated", name),
+                ),
+                Some(_) => (),
+                None => problem(format!("{}[{}]", key, idx), "name is missing".into()),
+            }
+        }
+    }
+    let is_target = |v: &str| names.contains(v) || BUILTIN_PROXIES.contains(&v);
+
+    // Check group members
+    let proxy_providers = get_keys(&value, "proxy-providers");
+    for (idx, group) in groups.iter().enumerate() {
+        for (i, member) in get_seq(group, "proxies").iter().enumerate() {
+            let member = member.as_str().unwrap_or_default();
+            if !is_target(member) {
+                problem(
+                    format!("proxy-groups[{}].proxies[{}]", idx, i),
+                    format!("proxy or group `{}` not found", member),
+                );
+            }
+        }
+        for (i, provider) in get_seq(group, "use").iter().enumerate() {
+            let provider = provider.as_str().unwrap_or_default();
+            if !proxy_providers.contains(provider) {
+                problem(
+                    format!("proxy-groups[{}].use[{}]", idx, i),
+                    format!("proxy provider `{}` not found", provider),
+                );
+            }
+        }
+    }
+
+    // Check rules
+    let rule_providers = get_keys(&value, "rule-providers");
+    let sub_rules = get_keys(&value, "sub-rules");
+    let rules = get_seq(&value, "rules");
+    for (idx, rule) in rules.iter().enumerate() {
+        let path = format!("rules[{}]", idx);
+        let Some(rule) = rule.as_str() else {
+            problem(path, "rule must be a string".into());
+            continue;
+        };
+        let Some((r#type, payload, target)) = split_rule(rule) else {
+            problem(path, format!("rule `{}` is malformed", rule));
+            continue;
+        };
+
+        if r#type == "SUB-RULE" {
+            if !sub_rules.contains(target) {
+                problem(path, format!("sub rule `{}` not found", target));
+            }
+            continue;
+        }
+        if r#type == "RULE-SET" && !rule_providers.contains(payload) {
+            problem(
+                path.clone(),
+                format!("rule provider `{}` not found", payload),
+            );
+        }
+        if !is_target(target) {
+            problem(path, format!("proxy or group `{}` not found", target));
+        }
+    }
+    let rule_mode = value
+        .get("mode")
+        .and_then(Value::as_str)
+        .is_none_or(|v| v.eq_ignore_ascii_case("rule"));
+    if rules.is_empty() && rule_mode {
+        problem(
+            "rules".into(),
+            "rules are empty, all traffic goes direct".into(),
+        );
+    }
+    if let Some(rule) = rules.last().and_then(Value::as_str) {
+        if split_rule(rule).is_some_and(|(v, _, _)| v != "MATCH") {
+            problem(
+                format!("rules[{}]", rules.len() - 1),
+                "rules must end with a `MATCH` rule".into(),
+            );
+        }
+    }
+
+    // Check ports, TCP and UDP ones separately
+    let mut listened = Vec::new();
+    for (key, tcp, udp) in PORT_FIELDS {
+        listened.push((key.to_owned(), value.get(key), tcp, udp));
+    }
+    if let Some(dns) = value.get("dns") {
+        listened.push(("dns.listen".into(), dns.get("listen"), false, true));
+    }
+    for (idx, listener) in get_seq(&value, "listeners").iter().enumerate() {
+        let r#type = listener
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let udp = UDP_LISTENERS.contains(&r#type);
+        let dual = DUAL_LISTENERS.contains(&r#type);
+        listened.push((
+            format!("listeners[{}].port", idx),
+            listener.get("port"),
+            !udp,
+            udp || dual,
+        ));
+    }
+    let mut tcp_ports = HashMap::<u64, String>::new();
+    let mut udp_ports = HashMap::<u64, String>::new();
+    for (path, port, tcp, udp) in listened {
+        let Some(port) = port.and_then(parse_port).filter(|v| *v > 0) else {
+            continue;
+        };
+        let other = [(tcp, &tcp_ports), (udp, &udp_ports)]
+            .into_iter()
+            .find_map(|(used, ports)| ports.get(&port).filter(|_| used));
+        if let Some(other) = other {
+            problem(
+                path.clone(),
+                format!("port `{}` collides with `{}`", port, other),
+            );
+        }
+
+        // Keep the first listener of a port, but still record the other protocol
+        if tcp {
+            tcp_ports.entry(port).or_insert_with(|| path.clone());
+        }
+        if udp {
+            udp_ports.entry(port).or_insert(path);
+        }
+    }
+
+    Ok(problems)
+}
+
+fn get_seq<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_sequence)
+        .map_or(&[], |v| v.as_slice())
+}
+
+fn get_keys<'a>(value: &'a Value, key: &str) -> HashSet<&'a str> {
+    value
+        .get(key)
+        .and_then(Value::as_mapping)
+        .map(|v| v.keys().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Split a rule into type, payload and target
+fn split_rule(rule: &str) -> Option<(&str, &str, &str)> {
+    let (r#type, rest) = rule.split_once(',').unwrap_or((rule, ""));
+    let r#type = r#type.trim();
+    if r#type == "MATCH" {
+        let target = rest.split(',').next()?.trim();
+        return (!target.is_empty()).then_some((r#type, "", target));
+    }
+
+    // Logic rules end their payload with a closing parenthesis
+    let (payload, rest) = if LOGIC_RULES.contains(&r#type) {
+        let end = rest.rfind(')')? + 1;
+        (&rest[..end], rest[end..].strip_prefix(',')?)
+    } else {
+        rest.split_once(',')?
+    };
+    let target = rest.split(',').next()?.trim();
+    (!target.is_empty()).then_some((r#type, payload.trim(), target))
+}
+
+/// Parse a port number, or the port of an address like `127.0.0.1:9090`
+fn parse_port(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(v) => v.as_u64(),
+        Value::String(v) => v.rsplit(':').next()?.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(contents: &str) -> Vec<String> {
+        validate_rendered(contents)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn split_rules() {
+        assert_eq!(
+            split_rule("DOMAIN-SUFFIX,example.com,Proxy"),
+            Some(("DOMAIN-SUFFIX", "example.com", "Proxy"))
+        );
+        assert_eq!(
+            split_rule("IP-CIDR6,2001:db8::/32,DIRECT,no-resolve"),
+            Some(("IP-CIDR6", "2001:db8::/32", "DIRECT"))
+        );
+        assert_eq!(
+            split_rule("AND,((DOMAIN,a.com),(NETWORK,UDP)),REJECT"),
+            Some(("AND", "((DOMAIN,a.com),(NETWORK,UDP))", "REJECT"))
+        );
+        assert_eq!(
+            split_rule("SUB-RULE,(NETWORK,tcp),sub"),
+            Some(("SUB-RULE", "(NETWORK,tcp)", "sub"))
+        );
+        assert_eq!(split_rule("MATCH,Proxy"), Some(("MATCH", "", "Proxy")));
+        assert_eq!(split_rule("DOMAIN,example.com"), None);
+        assert_eq!(split_rule("NOT,(DOMAIN,a.com"), None);
+    }
+
+    #[test]
+    fn check_references() {
+        let contents = r#"
+proxies:
+  - name: A
+proxy-groups:
+  - name: Proxy
+    proxies: [A, DIRECT, B]
+    use: [missing]
+rule-providers:
+  ads: {}
+sub-rules:
+  sub: [MATCH,DIRECT]
+rules:
+  - RULE-SET,ads,REJECT
+  - RULE-SET,other,Proxy
+  - OR,((DOMAIN,a.com),(DOMAIN,b.com)),Proxy
+  - SUB-RULE,(NETWORK,tcp),sub
+  - SUB-RULE,(NETWORK,udp),none
+  - IP-CIDR6,2001:db8::/32,Missing,no-resolve
+  - MATCH,Proxy
+"#;
+        assert_eq!(
+            problems(contents),
+            [
+                "`proxy-groups[0].proxies[2]`: proxy or group `B` not found",
+                "`proxy-groups[0].use[0]`: proxy provider `missing` not found",
+                "`rules[1]`: rule provider `other` not found",
+                "`rules[4]`: sub rule `none` not found",
+                "`rules[5]`: proxy or group `Missing` not found",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_empty_rules() {
+        assert_eq!(
+            problems("mode: rule"),
+            ["`rules`: rules are empty, all traffic goes direct"]
+        );
+        assert!(problems("mode: global").is_empty());
+        assert!(problems("mode: direct").is_empty());
+        assert_eq!(
+            problems("rules: ['DOMAIN,a.com']"),
+            ["`rules[0]`: rule `DOMAIN,a.com` is malformed"]
+        );
+    }
+
+    #[test]
+    fn check_port_collisions() {
+        let contents = r#"
+mode: direct
+mixed-port: 7890
+port: 7890
+external-controller: 127.0.0.1:9090
+dns:
+  listen: 0.0.0.0:7890
+listeners:
+  - type: http
+    port: 9090
+  - type: tuic
+    port: 9090
+  - type: shadowsocks
+    port: 1053
+  - type: hysteria2
+    port: 1053
+"#;
+        assert_eq!(
+            problems(contents),
+            [
+                "`mixed-port`: port `7890` collides with `port`",
+                "`dns.listen`: port `7890` collides with `mixed-port`",
+                "`listeners[0].port`: port `9090` collides with `external-controller`",
+                "`listeners[3].port`: port `1053` collides with `listeners[2].port`",
+            ]
+        );
+    }
+}
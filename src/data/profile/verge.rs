@@ -0,0 +1,207 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_yaml::Value;
+use url::Url;
+
+use super::{Config, ConfigProxy, ConfigType, Meta, DEFAULT_USER_AGENT};
+
+/// Profile imported from a Clash Verge installation
+pub struct VergeProfile {
+    pub conf: Config,
+    pub meta: Meta,
+    pub data: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Profiles {
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Item {
+    uid: Option<String>,
+    r#type: Option<String>,
+    name: Option<String>,
+    file: Option<String>,
+    url: Option<String>,
+    updated: Option<i64>,
+    extra: Option<Extra>,
+    option: Option<ItemOption>,
+}
+
+#[derive(Deserialize)]
+struct Extra {
+    upload: usize,
+    download: usize,
+    total: usize,
+    expire: i64,
+}
+
+#[derive(Deserialize)]
+struct ItemOption {
+    user_agent: Option<String>,
+    with_proxy: Option<bool>,
+    self_proxy: Option<bool>,
+    update_interval: Option<u64>,
+    danger_accept_invalid_certs: Option<bool>,
+    merge: Option<String>,
+    script: Option<String>,
+}
+
+/// Load remote and local profiles from a Clash Verge (Rev) data directory
+pub fn load_verge_profiles<P: AsRef<Path>>(dir: P) -> Result<Vec<VergeProfile>> {
+    let dir = dir.as_ref();
+
+    // Read profile list
+    let path = dir.join("profiles.yaml");
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("try to read file `{}`", path.display()))?;
+    let profiles = serde_yaml::from_str::<Profiles>(&contents)
+        .with_context(|| format!("try to parse file `{}`", path.display()))?;
+
+    // Read enhancement file by item uid
+    let read_file = |uid: &Option<String>| -> Result<Option<String>> {
+        let file = profiles
+            .items
+            .iter()
+            .find(|v| v.uid.is_some() && v.uid == *uid)
+            .and_then(|v| v.file.as_ref());
+        match file {
+            Some(file) => {
+                let path = dir.join("profiles").join(file);
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                let contents = fs::read_to_string(&path)
+                    .with_context(|| format!("try to read file `{}`", path.display()))?;
+                Ok(Some(contents))
+            }
+            None => Ok(None),
+        }
+    };
+
+    // Convert profiles
+    let mut result = Vec::new();
+    let mut names = HashSet::new();
+    for item in &profiles.items {
+        let r#type = match item.r#type.as_deref() {
+            Some("remote") => ConfigType::Remote,
+            Some("local") => ConfigType::Local,
+            _ => continue,
+        };
+        let option = item.option.as_ref();
+
+        // Extend configs from merge enhancement
+        let extend_configs = match read_file(&option.and_then(|v| v.merge.clone()))? {
+            Some(v) => match serde_yaml::from_str::<Value>(&v)? {
+                v @ Value::Mapping(_) => Some(v),
+                _ => None,
+            },
+            None => None,
+        };
+
+        // Extend scripts from script enhancement
+        let extend_scripts =
+            read_file(&option.and_then(|v| v.script.clone()))?.filter(|v| !v.trim().is_empty());
+
+        // Build config, suffixing duplicate names
+        let base = item
+            .name
+            .clone()
+            .or(item.uid.clone())
+            .unwrap_or("Clash Verge profile".into());
+        let mut name = base.clone();
+        let mut idx = 1;
+        while names.contains(&name) {
+            idx += 1;
+            name = format!("{} {}", base, idx);
+        }
+        names.insert(name.clone());
+        let remote = r#type == ConfigType::Remote;
+        let conf = Config {
+            uuid: String::new(),
+            name: name.clone(),
+            r#type,
+            url: match &item.url {
+                Some(v) if remote => {
+                    Some(Url::parse(v).with_context(|| format!("try to parse URL of `{}`", name))?)
+                }
+                _ => None,
+            },
+            url_file: None,
+            url_env: None,
+            user_agent: if remote {
+                Some(
+                    option
+                        .and_then(|v| v.user_agent.clone())
+                        .unwrap_or(DEFAULT_USER_AGENT.to_owned()),
+                )
+            } else {
+                None
+            },
+            path: None,
+            update_interval: option
+                .and_then(|v| v.update_interval)
+                .filter(|v| remote && *v > 0),
+            profiles: Vec::new(),
+            pool: None,
+            template: None,
+            use_proxy: match option {
+                Some(v) if v.self_proxy == Some(true) => ConfigProxy::Mihomo,
+                Some(v) if v.with_proxy == Some(true) => ConfigProxy::System,
+                _ => ConfigProxy::None,
+            },
+            proxy_fallback: false,
+            allow_invalid_certs: option
+                .and_then(|v| v.danger_accept_invalid_certs)
+                .unwrap_or(false),
+            timeout: None,
+            retries: None,
+            retry_backoff: None,
+            headers: Default::default(),
+            basic_auth: None,
+            bearer_token: None,
+            extend_configs,
+            extend_scripts,
+        };
+        conf.verify()
+            .with_context(|| format!("try to verify profile `{}`", name))?;
+
+        // Build metadata
+        let extra = item.extra.as_ref();
+        let meta = Meta {
+            name,
+            remote,
+            updated_at: item.updated.filter(|v| *v > 0),
+            expired_at: extra.map(|v| v.expire).filter(|v| *v > 0),
+            used_bytes: extra.map(|v| v.upload + v.download),
+            upload_bytes: extra.map(|v| v.upload),
+            download_bytes: extra.map(|v| v.download),
+            total_bytes: extra.map(|v| v.total),
+            ..Default::default()
+        };
+
+        // Read data
+        let data = match &item.file {
+            Some(file) => {
+                let path = dir.join("profiles").join(file);
+                if path.is_file() {
+                    Some(
+                        fs::read_to_string(&path)
+                            .with_context(|| format!("try to read file `{}`", path.display()))?,
+                    )
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+
+        result.push(VergeProfile { conf, meta, data });
+    }
+
+    Ok(result)
+}
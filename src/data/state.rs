@@ -0,0 +1,115 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::profile::{Config as ProfileConfig, Meta},
+    utils::{self, file, path},
+};
+
+/// Max count of kept activation logs
+const ACTIVATION_LOG_LIMIT: usize = 100;
+
+/// Mihomosh runtime state
+#[derive(Default, Deserialize, Serialize)]
+pub struct State {
+    /// UUID of the last activated profile
+    pub active: Option<String>,
+
+    /// Activation time of the last activated profile
+    pub activated_at: Option<i64>,
+
+    /// Hash of the rendered Mihomo config at activation
+    pub output_hash: Option<String>,
+
+    /// Hash of the profile data at activation
+    pub data_hash: Option<String>,
+
+    /// Activation logs in ascending order
+    #[serde(default)]
+    pub activations: Vec<Activation>,
+}
+impl State {
+    /// Get path
+    fn get_path() -> PathBuf {
+        path::get_data_dir().join("state.json")
+    }
+
+    /// Load from state file
+    ///
+    /// Always read from disk since the state could be changed by other processes
+    pub fn load() -> Result<Self> {
+        let path = Self::get_path();
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("try to read file `{}`", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("try to parse file `{}`", path.display()))
+    }
+
+    /// Flush to state file
+    pub fn flush(&self) -> Result<()> {
+        let path = Self::get_path();
+        file::write_private(&path, serde_json::to_string(self)?)
+            .with_context(|| format!("try to write file `{}`", path.display()))
+    }
+
+    /// Record a profile activation with its rendered Mihomo config
+    pub fn record_activation(
+        &mut self,
+        meta: &Meta,
+        conf: &ProfileConfig,
+        output: &str,
+    ) -> Result<()> {
+        let activation = Activation {
+            uuid: meta.uuid.clone(),
+            name: meta.name.clone(),
+            activated_at: Utc::now().timestamp(),
+            output_hash: utils::hash(output),
+        };
+
+        self.active = Some(activation.uuid.clone());
+        self.activated_at = Some(activation.activated_at);
+        self.output_hash = Some(activation.output_hash.clone());
+        self.data_hash = Some(utils::hash(conf.read_data()?));
+
+        self.activations.push(activation);
+        if self.activations.len() > ACTIVATION_LOG_LIMIT {
+            let count = self.activations.len() - ACTIVATION_LOG_LIMIT;
+            self.activations.drain(..count);
+        }
+
+        Ok(())
+    }
+
+    /// Whether the profile is active and its data changed since activation
+    pub fn is_data_changed(&self, conf: &ProfileConfig) -> Result<bool> {
+        if self.active.as_deref() != Some(conf.uuid.as_str()) {
+            return Ok(false);
+        }
+
+        Ok(self.data_hash.as_deref() != Some(utils::hash(conf.read_data()?).as_str()))
+    }
+}
+
+/// Activation log
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Activation {
+    pub uuid: String,
+    pub name: String,
+    pub activated_at: i64,
+    pub output_hash: String,
+}
+impl Activation {
+    pub fn get_styled_activated_at(&self) -> String {
+        Utc.timestamp_opt(self.activated_at, 0)
+            .unwrap()
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    }
+}
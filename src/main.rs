@@ -7,49 +7,86 @@ use anyhow::Result;
 use arg::{
     config::Command as ConfigCommand, ctrl::Command as CtrlCommand,
     profile::Command as ProfileCommand, show::Command as ShowCommand,
-    status::Command as StatusCommand, Args,
+    status::Command as StatusCommand, Args, Command,
 };
 use clap::Parser;
-use cmd::{config, ctrl, profile, show, status, test};
+use cmd::{config, ctrl, daemon, profile, show, status, test};
+use utils::prompt;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Parse commands
     let args = Args::parse();
-    match args {
-        Args::Config(cmd) => match cmd {
+    prompt::set_assume_yes(args.yes);
+    utils::set_show_secrets(args.show_secrets);
+    match args.command {
+        Command::Config(cmd) => match cmd {
             ConfigCommand::Edit { editor } => config::edit(editor)?,
             ConfigCommand::Reset => config::reset()?,
             ConfigCommand::View => config::view()?,
         },
-        Args::Ctrl(cmd) => match cmd {
+        Command::Ctrl(cmd) => match cmd {
             CtrlCommand::UpdateGeo => ctrl::update_geo().await?,
             CtrlCommand::UpdateGroup => ctrl::update_group().await?,
             CtrlCommand::Restart => ctrl::restart().await?,
         },
-        Args::Profile(cmd) => match cmd {
-            ProfileCommand::Activate { uuid_or_name } => profile::activate(uuid_or_name).await?,
+        Command::Daemon => daemon::daemon().await?,
+        Command::Profile(cmd) => match cmd {
+            ProfileCommand::Activate {
+                uuid_or_name,
+                watch,
+                dry_run,
+                force,
+                method,
+                localize,
+            } => profile::activate(uuid_or_name, watch, dry_run, force, method, localize).await?,
+            ProfileCommand::Activations { output } => profile::activations(output)?,
+            ProfileCommand::Current { output } => profile::current(output)?,
             ProfileCommand::Delete { uuid_or_name } => profile::delete(uuid_or_name)?,
-            ProfileCommand::EditConfigs { uuid_or_name } => profile::edit_conf(uuid_or_name)?,
-            ProfileCommand::EditData { uuid_or_name } => profile::edit_data(uuid_or_name)?,
-            ProfileCommand::List => profile::list()?,
-            ProfileCommand::New => profile::new()?,
+            ProfileCommand::Diff {
+                uuid_or_name,
+                old,
+                new,
+            } => profile::diff(uuid_or_name, old, new)?,
+            ProfileCommand::EditConfigs { uuid_or_name, conf } => {
+                profile::edit_conf(uuid_or_name, conf)?
+            }
+            ProfileCommand::EditData {
+                uuid_or_name,
+                from_file,
+            } => profile::edit_data(uuid_or_name, from_file)?,
+            ProfileCommand::Export {
+                uuid_or_names,
+                output,
+                strip_secrets,
+            } => profile::export(uuid_or_names, output, strip_secrets)?,
+            ProfileCommand::History { uuid_or_name } => profile::history(uuid_or_name)?,
+            ProfileCommand::Import { bundle } => profile::import(bundle)?,
+            ProfileCommand::ImportVerge { dir } => profile::import_verge(dir)?,
+            ProfileCommand::Info { uuid_or_name } => profile::info(uuid_or_name)?,
+            ProfileCommand::List { output } => profile::list(output)?,
+            ProfileCommand::New { conf } => profile::new(conf).await?,
+            ProfileCommand::Render {
+                uuid_or_name,
+                output,
+            } => profile::render(uuid_or_name, output)?,
+            ProfileCommand::Rollback { uuid_or_name, rev } => profile::rollback(uuid_or_name, rev)?,
             ProfileCommand::Update { uuid_or_name } => profile::update(uuid_or_name).await?,
             ProfileCommand::ViewConfigs { uuid_or_name } => profile::view_conf(uuid_or_name)?,
             ProfileCommand::ViewData { uuid_or_name } => profile::view_data(uuid_or_name)?,
             ProfileCommand::ViewRules { uuid_or_name } => profile::view_rules(uuid_or_name)?,
         },
-        Args::Show(cmd) => match cmd {
+        Command::Show(cmd) => match cmd {
             ShowCommand::Profile => show::profile()?,
-            ShowCommand::Rules => show::rules()?,
+            ShowCommand::Rules { output } => show::rules(output)?,
         },
-        Args::Status(cmd) => match cmd {
+        Command::Status(cmd) => match cmd {
             StatusCommand::Configs => status::configs().await?,
-            StatusCommand::Connections => status::connections().await?,
-            StatusCommand::Groups => status::groups().await?,
-            StatusCommand::Version => status::version().await?,
+            StatusCommand::Connections { output } => status::connections(output).await?,
+            StatusCommand::Groups { output } => status::groups(output).await?,
+            StatusCommand::Version { output } => status::version(output).await?,
         },
-        Args::Test { url } => test::test(url).await?,
+        Command::Test { url } => test::test(url).await?,
     }
 
     // Success
@@ -1,9 +1,24 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use reqwest::{Client, Method, RequestBuilder};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio::time::{self, Instant};
 use url::Url;
 
+/// Window to see Mihomo go down after a restart request
+///
+/// Mihomo restarts by executing itself again, which may finish before the first check, so it is
+/// assumed to be restarted if it is not seen down within this window
+const RESTART_DOWN_WINDOW: Duration = Duration::from_secs(2);
+
+/// Interval and per-request timeout of checks while waiting for Mihomo to go down
+const RESTART_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Interval and per-request timeout of health checks
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
 pub struct Api {
     entry_point: String,
     auth_token: Option<String>,
@@ -47,6 +62,40 @@ impl Api {
         Ok(r.version)
     }
 
+    /// Wait until Mihomo responds to version requests, returns whether it is healthy
+    pub async fn wait_healthy(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if let Ok(Ok(_)) = time::timeout(HEALTH_CHECK_INTERVAL, self.get_version()).await {
+                return true;
+            }
+            time::sleep(HEALTH_CHECK_INTERVAL).await;
+        }
+
+        false
+    }
+
+    /// Wait until Mihomo goes down and comes back after a restart request, returns whether it is
+    /// healthy
+    ///
+    /// Versions answered before Mihomo is seen down come from the old process and are ignored
+    pub async fn wait_restarted(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+        let window = start + RESTART_DOWN_WINDOW.min(timeout);
+        while Instant::now() < window {
+            if !matches!(
+                time::timeout(RESTART_CHECK_INTERVAL, self.get_version()).await,
+                Ok(Ok(_))
+            ) {
+                break;
+            }
+            time::sleep(RESTART_CHECK_INTERVAL).await;
+        }
+
+        self.wait_healthy(timeout.saturating_sub(start.elapsed()))
+            .await
+    }
+
     pub async fn get_configs(&self) -> Result<Value> {
         // Get response
         let body = self
@@ -71,6 +120,25 @@ impl Api {
         Ok(())
     }
 
+    pub async fn reload<S: AsRef<str>>(&self, payload: S) -> Result<()> {
+        #[derive(Serialize)]
+        struct Body {
+            payload: String,
+        }
+
+        // Get response
+        self.create_client(Method::PUT, "/configs?force=true")?
+            .body(serde_json::to_string(&Body {
+                payload: payload.as_ref().to_owned(),
+            })?)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        // Success
+        Ok(())
+    }
+
     pub async fn upgrade_geo(&self) -> Result<()> {
         // Get response
         self.create_client(Method::POST, "/upgrade/geo")?
@@ -119,6 +187,19 @@ impl Api {
         Ok(())
     }
 
+    /// Update a provider, `kind` is either `rules` or `proxies`
+    pub async fn update_provider<S: AsRef<str>>(&self, kind: &str, name: S) -> Result<()> {
+        // Get response
+        let name = urlencoding::encode(name.as_ref());
+        self.create_client(Method::PUT, format!("/providers/{kind}/{name}"))?
+            .send()
+            .await?
+            .error_for_status()?;
+
+        // Success
+        Ok(())
+    }
+
     pub async fn get_connections(&self) -> Result<Value> {
         // Get response
         let body = self
@@ -0,0 +1,36 @@
+use similar::TextDiff;
+
+pub fn unified<S1, S2, S3, S4>(old: S1, new: S2, old_name: S3, new_name: S4) -> String
+where
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+    S3: AsRef<str>,
+    S4: AsRef<str>,
+{
+    // Create diff
+    let diff = TextDiff::from_lines(old.as_ref(), new.as_ref());
+    let contents = diff
+        .unified_diff()
+        .header(old_name.as_ref(), new_name.as_ref())
+        .to_string();
+
+    // Colorize lines
+    let mut buf = String::new();
+    for line in contents.lines() {
+        let line = if line.starts_with("---") || line.starts_with("+++") {
+            console::style(line).bold().to_string()
+        } else if line.starts_with("@@") {
+            console::style(line).cyan().to_string()
+        } else if line.starts_with('-') {
+            console::style(line).red().to_string()
+        } else if line.starts_with('+') {
+            console::style(line).green().to_string()
+        } else {
+            line.to_owned()
+        };
+        buf.push_str(&line);
+        buf.push('\n');
+    }
+
+    buf
+}
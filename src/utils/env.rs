@@ -0,0 +1,179 @@
+use std::{collections::HashMap, env, sync::LazyLock};
+
+use anyhow::{bail, Result};
+use regex::{Captures, Regex};
+use serde_yaml::Value;
+
+/// Pattern of `${VAR}` and `${VAR:-default}` references
+static REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}").unwrap());
+
+/// Pattern of plain decimal numbers, without leading zeros, hex or exponents
+static DECIMAL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^-?(0|[1-9][0-9]*)(\.[0-9]+)?$").unwrap());
+
+/// Expand `${VAR}` and `${VAR:-default}` references
+///
+/// Environment variables take precedence over the given variables,
+/// the default is used when neither is set
+pub fn expand<S: AsRef<str>>(s: S, vars: &HashMap<String, String>) -> Result<String> {
+    let mut missing = None;
+    let value = REFERENCE.replace_all(s.as_ref(), |caps: &Captures| {
+        if let Ok(v) = env::var(&caps[1]) {
+            return v;
+        }
+        if let Some(v) = vars.get(&caps[1]) {
+            return v.clone();
+        }
+        if let Some(v) = caps.get(2) {
+            return v.as_str().to_owned();
+        }
+        missing.get_or_insert(caps[1].to_owned());
+        String::new()
+    });
+    if let Some(name) = missing {
+        bail!("variable `{}` is not set", name);
+    }
+
+    Ok(value.into_owned())
+}
+
+/// Expand references in every string of a YAML value
+///
+/// A string consisting of a single reference is converted if it expands to a plain decimal
+/// number or a boolean, so that `port: ${PORT}` still gives a number. Other strings are kept.
+pub fn expand_value(value: &mut Value, vars: &HashMap<String, String>) -> Result<()> {
+    match value {
+        Value::String(s) => {
+            let expanded = expand(&*s, vars)?;
+            if expanded == *s {
+                return Ok(());
+            }
+            let single = REFERENCE
+                .find(s)
+                .is_some_and(|v| v.start() == 0 && v.end() == s.len());
+            *value = match serde_yaml::from_str::<Value>(&expanded) {
+                Ok(v @ Value::Number(_)) if single && DECIMAL.is_match(&expanded) => v,
+                Ok(v @ Value::Bool(_)) if single && ["true", "false"].contains(&&*expanded) => v,
+                _ => Value::String(expanded),
+            };
+        }
+        Value::Sequence(seq) => {
+            for v in seq {
+                expand_value(v, vars)?;
+            }
+        }
+        Value::Mapping(map) => {
+            for (_, v) in map.iter_mut() {
+                expand_value(v, vars)?;
+            }
+        }
+        Value::Tagged(tagged) => expand_value(&mut tagged.value, vars)?,
+        _ => (),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> HashMap<String, String> {
+        [("HOST", "example.com"), ("PORT", "7890"), ("PATH_A", "/a")]
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn expand_references() {
+        let vars = vars();
+        assert_eq!(
+            expand("https://${HOST}:${PORT}${PATH_A}", &vars).unwrap(),
+            "https://example.com:7890/a"
+        );
+        assert_eq!(expand("${MIHOMOSH_TEST_UNSET:-}", &vars).unwrap(), "");
+        assert_eq!(
+            expand("${MIHOMOSH_TEST_UNSET:-a b} ${HOST:-other}", &vars).unwrap(),
+            "a b example.com"
+        );
+        assert_eq!(expand("$HOST ${ HOST}", &vars).unwrap(), "$HOST ${ HOST}");
+    }
+
+    #[test]
+    fn expand_missing() {
+        let err = expand("${HOST} ${MIHOMOSH_TEST_MISSING}", &vars()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "variable `MIHOMOSH_TEST_MISSING` is not set"
+        );
+    }
+
+    #[test]
+    fn expand_prefers_env() {
+        let mut vars = vars();
+        vars.insert("MIHOMOSH_TEST_ENV".into(), "vars".into());
+        assert_eq!(expand("${MIHOMOSH_TEST_ENV}", &vars).unwrap(), "vars");
+        env::set_var("MIHOMOSH_TEST_ENV", "env");
+        assert_eq!(
+            expand("${MIHOMOSH_TEST_ENV:-default}", &vars).unwrap(),
+            "env"
+        );
+        env::remove_var("MIHOMOSH_TEST_ENV");
+    }
+
+    #[test]
+    fn expand_value_conversion() {
+        let vars = [
+            ("INT", "7890"),
+            ("FLOAT", "2.0"),
+            ("BOOL", "true"),
+            ("ZERO", "0123"),
+            ("HEX", "0x1F"),
+            ("EXP", "1e3"),
+            ("YES", "yes"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect();
+
+        let mut value = serde_yaml::from_str::<Value>(
+            r#"
+int: ${INT}
+float: ${FLOAT}
+bool: ${BOOL}
+zero: ${ZERO}
+hex: ${HEX}
+exp: ${EXP}
+yes: ${YES}
+mixed: ${INT}${INT}
+prefixed: v${FLOAT}
+nested:
+  - ${INT}
+  - key: ${BOOL:-false}
+plain: "0123"
+"#,
+        )
+        .unwrap();
+        expand_value(&mut value, &vars).unwrap();
+
+        let expected = serde_yaml::from_str::<Value>(
+            r#"
+int: 7890
+float: 2.0
+bool: true
+zero: "0123"
+hex: "0x1F"
+exp: "1e3"
+yes: "yes"
+mixed: "78907890"
+prefixed: v2.0
+nested: [7890, {key: true}]
+plain: "0123"
+"#,
+        )
+        .unwrap();
+        assert_eq!(value, expected);
+    }
+}
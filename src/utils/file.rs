@@ -1,6 +1,11 @@
-use std::{env, fs, io::Write, path::Path, process::Command};
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Write},
+    path::Path,
+    process::Command,
+};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use tempfile::NamedTempFile;
 
 use super::highlight;
@@ -39,6 +44,67 @@ where
     Ok(fs::read_to_string(path)?)
 }
 
+/// Read contents from a file, or from stdin if the path is `-`
+pub fn read_input<P: AsRef<Path>>(path: P) -> Result<String> {
+    let path = path.as_ref();
+    if path.as_os_str() == "-" {
+        return Ok(io::read_to_string(io::stdin())?);
+    }
+
+    Ok(fs::read_to_string(path)?)
+}
+
+pub fn write_atomic<P, C>(path: P, contents: C) -> Result<()>
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    // Create temporary file next to the target, so that renaming stays on one filesystem
+    let dir = path
+        .as_ref()
+        .parent()
+        .filter(|v| !v.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut temp_file = NamedTempFile::new_in(dir)?;
+
+    // Set contents
+    temp_file.write_all(contents.as_ref())?;
+    temp_file.as_file().sync_all()?;
+
+    // Replace target
+    temp_file.persist(path.as_ref())?;
+
+    // Success
+    Ok(())
+}
+
+/// Write contents atomically, readable and writable only by the owner
+pub fn write_private<P, C>(path: P, contents: C) -> Result<()>
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    write_atomic(&path, contents)?;
+
+    // Temporary files are already private, set it explicitly anyway
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(())
+}
+
+/// Read a secret from a file, with `~/` expanded and surrounding whitespaces trimmed
+pub fn read_secret<S: AsRef<str>>(path: S) -> Result<String> {
+    let path = super::path::expand_home(path);
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("try to read file `{}`", path.display()))?;
+
+    Ok(contents.trim().to_owned())
+}
+
 pub fn show_file<P: AsRef<Path>>(path: P) -> Result<()> {
     // Highlight contents
     let contents = highlight::from_file(&path)?;
@@ -48,7 +114,7 @@ pub fn show_file<P: AsRef<Path>>(path: P) -> Result<()> {
         .as_ref()
         .file_name()
         .map_or("", |v| v.to_str().unwrap_or(""));
-    show_contents(&name, &contents, false)?;
+    show_contents(name, &contents, false)?;
 
     // Success
     Ok(())
@@ -59,6 +125,12 @@ where
     S1: AsRef<str>,
     S2: AsRef<str>,
 {
+    // Print plain contents if not a terminal
+    if !io::stdout().is_terminal() {
+        print!("{}", console::strip_ansi_codes(contents.as_ref()));
+        return Ok(());
+    }
+
     // Create temporary file
     let mut temp_file = NamedTempFile::with_suffix(format!("-{}", suffix.as_ref()))?;
 
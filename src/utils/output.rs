@@ -0,0 +1,28 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Output format of listing and status commands
+#[derive(Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Styled text for humans
+    #[default]
+    Table,
+
+    /// JSON document
+    Json,
+
+    /// YAML document
+    Yaml,
+}
+
+/// Print a value in a machine-readable format
+pub fn print_serialized<T: Serialize>(value: &T, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Table => unreachable!("table output must be printed by the caller"),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+    }
+
+    Ok(())
+}
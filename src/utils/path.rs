@@ -1,6 +1,15 @@
 use std::{fs, path::PathBuf, sync::OnceLock};
 
-use directories::ProjectDirs;
+use directories::{BaseDirs, ProjectDirs};
+
+/// Expand a leading `~/` to the home directory
+pub fn expand_home<S: AsRef<str>>(path: S) -> PathBuf {
+    let path = path.as_ref();
+    match path.strip_prefix("~/") {
+        Some(rest) => BaseDirs::new().map_or(PathBuf::from(path), |v| v.home_dir().join(rest)),
+        None => PathBuf::from(path),
+    }
+}
 
 pub fn get_project_dir() -> &'static ProjectDirs {
     static I: OnceLock<ProjectDirs> = OnceLock::new();
@@ -15,7 +24,7 @@ pub fn get_data_dir() -> &'static PathBuf {
     I.get_or_init(|| {
         let dir = get_project_dir().data_local_dir();
         if !dir.is_dir() {
-            fs::create_dir_all(&dir).expect("fail to create local data directory")
+            fs::create_dir_all(dir).expect("fail to create local data directory")
         }
 
         dir.into()
@@ -45,3 +54,15 @@ pub fn get_profile_data_dir() -> &'static PathBuf {
         dir
     })
 }
+
+pub fn get_profile_history_dir() -> &'static PathBuf {
+    static I: OnceLock<PathBuf> = OnceLock::new();
+    I.get_or_init(|| {
+        let dir = get_data_dir().join("profile_history");
+        if !dir.is_dir() {
+            fs::create_dir_all(&dir).expect("fail to create profile history directory")
+        }
+
+        dir
+    })
+}
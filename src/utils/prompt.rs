@@ -1,8 +1,18 @@
-use std::io::{self, BufRead, Write};
+use std::{
+    io::{self, BufRead, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 use anyhow::Result;
 use regex::Regex;
 
+/// Whether to answer yes to all confirm prompts
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+pub fn set_assume_yes(value: bool) {
+    ASSUME_YES.store(value, Ordering::Relaxed);
+}
+
 pub fn ask<S: AsRef<str>>(prompt: S) -> Result<String> {
     // Print prompt
     print!("{}", prompt.as_ref());
@@ -17,6 +27,10 @@ pub fn ask<S: AsRef<str>>(prompt: S) -> Result<String> {
 }
 
 pub fn confirm<S: AsRef<str>>(prompt: S) -> Result<bool> {
+    if ASSUME_YES.load(Ordering::Relaxed) {
+        return Ok(true);
+    }
+
     // Get input
     let input = ask(console::style(format!("{} (y/N): ", prompt.as_ref()))
         .bold()
@@ -26,5 +40,5 @@ pub fn confirm<S: AsRef<str>>(prompt: S) -> Result<bool> {
 
     // Check input
     let re = Regex::new(r"^[yY]$")?;
-    Ok(re.is_match(&input.trim()))
+    Ok(re.is_match(input.trim()))
 }